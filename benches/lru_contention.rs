@@ -0,0 +1,66 @@
+//! 多线程争用下对比单线程处理的LRUCacheHandle与分片加锁的ShardedLru
+//!
+//! cargo bench --bench lru_contention
+use std::time::{Duration, Instant};
+
+use algorithm::sort::lru_lockfree::{ConcurrentCache, LRUCacheHandle};
+use algorithm::sort::lru_sharded::ShardedLru;
+
+const CAPACITY: usize = 10_000;
+const KEYS: u64 = 20_000;
+const OPS_PER_THREAD: usize = 50_000;
+
+// 简单的线性同余，避免引入rand
+fn next(state: &mut u64) -> u64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    *state >> 33
+}
+
+// 每个线程80%读20%写，返回总耗时。计时前先填满缓存，读操作按容量比例命中
+fn run<C: ConcurrentCache<u64, u64> + 'static>(cache: C, threads: usize) -> Duration {
+    cache.put_many((0..KEYS).map(|k| (k, k)).collect()).unwrap();
+    let start = Instant::now();
+    let handles: Vec<_> = (0..threads)
+        .map(|t| {
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                let mut state = t as u64 + 1;
+                for _ in 0..OPS_PER_THREAD {
+                    // 读写和key分别取随机数，避免两者相关
+                    let write = next(&mut state).is_multiple_of(5);
+                    let key = next(&mut state) % KEYS;
+                    if write {
+                        cache.put(key, key).unwrap();
+                    } else {
+                        cache.get(key).unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+    start.elapsed()
+}
+
+fn report(name: &str, threads: usize, elapsed: Duration) {
+    let ops = (threads * OPS_PER_THREAD) as f64;
+    println!(
+        "{name:>8} threads={threads:<3} {:>10.0} ops/s ({elapsed:?})",
+        ops / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let max = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let mut threads = 1;
+    while threads <= max.max(4) {
+        let elapsed = run(LRUCacheHandle::new(CAPACITY), threads);
+        report("actor", threads, elapsed);
+
+        let elapsed = run(ShardedLru::new(CAPACITY), threads);
+        report("sharded", threads, elapsed);
+        threads *= 2;
+    }
+}
@@ -0,0 +1,159 @@
+use std::hash::Hash;
+
+use super::Cache;
+use crate::sort::lru::LRUCache;
+
+/// Adaptive Replacement Cache
+///
+/// * T1：只访问过一次的元素，T2：访问过多次的元素
+/// * B1/B2：分别记录从T1/T2淘汰的key（幽灵队列）
+/// * 在B1中命中说明T1太小，调大T1的目标大小p；在B2中命中则调小p
+pub struct ARCCache<K, V> {
+    t1: LRUCache<K, V>,
+    t2: LRUCache<K, V>,
+    b1: LRUCache<K, ()>,
+    b2: LRUCache<K, ()>,
+    // T1的目标大小
+    p: usize,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> ARCCache<K, V> {
+    pub fn new_with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            t1: LRUCache::unbounded(),
+            t2: LRUCache::unbounded(),
+            b1: LRUCache::unbounded(),
+            b2: LRUCache::unbounded(),
+            p: 0,
+            capacity,
+        }
+    }
+
+    // 缓存已满时按p从T1或T2淘汰一个，key记入对应的幽灵队列
+    fn replace(&mut self, in_b2: bool) -> Option<(K, V)> {
+        if self.t1.len() + self.t2.len() < self.capacity {
+            return None;
+        }
+        let t1_len = self.t1.len();
+        if t1_len > 0 && (t1_len > self.p || (in_b2 && t1_len == self.p)) || self.t2.is_empty() {
+            let (k, v) = self.t1.pop_lru()?;
+            self.b1.put(k.clone(), ());
+            Some((k, v))
+        } else {
+            let (k, v) = self.t2.pop_lru()?;
+            self.b2.put(k.clone(), ());
+            Some((k, v))
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for ARCCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        let c = self.capacity;
+        // 再次访问，进入T2
+        if self.t1.remove(&key).is_some() || self.t2.contains_key(&key) {
+            self.t2.put(key, value);
+            return None;
+        }
+
+        // 幽灵命中，调整p后进入T2
+        if self.b1.contains_key(&key) {
+            let delta = (self.b2.len() / self.b1.len()).max(1);
+            self.p = (self.p + delta).min(c);
+            self.b1.remove(&key);
+            let evicted = self.replace(false);
+            self.t2.put(key, value);
+            return evicted;
+        }
+        if self.b2.contains_key(&key) {
+            let delta = (self.b1.len() / self.b2.len()).max(1);
+            self.p = self.p.saturating_sub(delta);
+            self.b2.remove(&key);
+            let evicted = self.replace(true);
+            self.t2.put(key, value);
+            return evicted;
+        }
+
+        // 全新的key，保持|T1|+|B1|<=c，总记录数<=2c
+        let mut evicted = None;
+        if self.t1.len() + self.b1.len() >= c {
+            if self.t1.len() < c {
+                self.b1.pop_lru();
+                evicted = self.replace(false);
+            } else {
+                evicted = self.t1.pop_lru();
+            }
+        } else {
+            let total = self.t1.len() + self.t2.len() + self.b1.len() + self.b2.len();
+            if total >= c {
+                if total >= 2 * c {
+                    self.b2.pop_lru();
+                }
+                evicted = self.replace(false);
+            }
+        }
+        self.t1.put(key, value);
+        evicted
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        if let Some(v) = self.t1.remove(key) {
+            self.t2.put(key.clone(), v);
+            return self.t2.peek(key);
+        }
+        self.t2.get_ref(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.t1.peek(key).or_else(|| self.t2.peek(key))
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.t1.remove(key).or_else(|| self.t2.remove(key))
+    }
+
+    fn len(&self) -> usize {
+        self.t1.len() + self.t2.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn clear(&mut self) {
+        self.t1.clear();
+        self.t2.clear();
+        self.b1.clear();
+        self.b2.clear();
+        self.p = 0;
+    }
+}
+
+#[test]
+fn test_arc() {
+    let mut cache = ARCCache::new_with_capacity(4);
+    for i in 0..4 {
+        cache.put(i, i);
+    }
+    // 0、1访问过两次，进入T2
+    cache.get(&0);
+    cache.get(&1);
+    assert_eq!(cache.t2.len(), 2);
+
+    // 扫描只在T1中轮转，淘汰的key进入B1
+    for i in 100..120 {
+        cache.put(i, i);
+    }
+    assert_eq!(cache.peek(&0), Some(&0));
+    assert_eq!(cache.peek(&1), Some(&1));
+    assert_eq!(cache.len(), 4);
+
+    // B1命中，T1的目标大小变大
+    assert!(cache.b1.contains_key(&117));
+    cache.put(117, 117);
+    assert_eq!(cache.p, 1);
+    assert!(cache.t2.contains_key(&117));
+    assert_eq!(cache.len(), 4);
+}
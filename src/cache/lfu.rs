@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::Cache;
+use crate::collections::doubly_linked_list::{DoublyLinkedList, NodeHandle};
+use crate::sort::lru::LRUCache;
+
+/// Least Frequently Used，淘汰访问次数最少的元素，次数相同时淘汰最久未使用的
+///
+/// 每个访问次数对应一个桶，桶内按使用先后排序。桶按次数从小到大串成链表，
+/// 头部即最小次数，访问时移到相邻的下一个桶，增删查都是O(1)
+pub struct LFUCache<K, V> {
+    // key -> (value, 所在的桶)
+    entries: HashMap<K, (V, NodeHandle<Bucket<K>>)>,
+    // 按访问次数递增，不保留空桶
+    buckets: DoublyLinkedList<Bucket<K>>,
+    capacity: usize,
+}
+
+struct Bucket<K> {
+    freq: usize,
+    keys: LRUCache<K, ()>,
+}
+
+impl<K: Hash + Eq> Bucket<K> {
+    fn new(freq: usize) -> Self {
+        Self {
+            freq,
+            keys: LRUCache::unbounded(),
+        }
+    }
+}
+
+// SAFETY: 桶句柄只指向self.buckets内的节点，不会泄露到外部
+unsafe impl<K: Send, V: Send> Send for LFUCache<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LFUCache<K, V> {}
+
+impl<K: Hash + Eq + Clone, V> LFUCache<K, V> {
+    pub fn new_with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            entries: HashMap::with_capacity(capacity),
+            buckets: DoublyLinkedList::new(),
+            capacity,
+        }
+    }
+
+    /// 元素的访问次数，新插入为1
+    pub fn frequency(&self, key: &K) -> Option<usize> {
+        let (_, bucket) = self.entries.get(key)?;
+        // SAFETY: entries中的句柄都指向buckets中存活的节点，下同
+        Some(unsafe { self.buckets.get(*bucket) }.freq)
+    }
+
+    // 访问次数+1，换到下一个桶，没有则在其后新建
+    fn touch(&mut self, key: &K) {
+        let Some((_, bucket)) = self.entries.get_mut(key) else {
+            return;
+        };
+        let old = *bucket;
+        let next = unsafe {
+            let freq = self.buckets.get(old).freq + 1;
+            match self.buckets.next_node(old) {
+                Some(next) if self.buckets.get(next).freq == freq => next,
+                _ => self.buckets.insert_after_node(old, Bucket::new(freq)),
+            }
+        };
+        *bucket = next;
+        unsafe { self.buckets.get_mut(next) }
+            .keys
+            .put(key.clone(), ());
+        self.unlink(key, old);
+    }
+
+    // 从桶中删除，桶空时一并删除
+    fn unlink(&mut self, key: &K, bucket: NodeHandle<Bucket<K>>) {
+        let keys = &mut unsafe { self.buckets.get_mut(bucket) }.keys;
+        keys.remove(key);
+        if keys.is_empty() {
+            unsafe { self.buckets.remove_node(bucket) };
+        }
+    }
+
+    fn evict(&mut self) -> Option<(K, V)> {
+        // 头部的桶次数最少
+        let bucket = self.buckets.front_node()?;
+        let (key, _) = unsafe { self.buckets.get(bucket) }.keys.peek_lru()?;
+        let key = key.clone();
+        let (value, _) = self.entries.remove(&key)?;
+        self.unlink(&key, bucket);
+        Some((key, value))
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for LFUCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some((v, _)) = self.entries.get_mut(&key) {
+            *v = value;
+            self.touch(&key);
+            return None;
+        }
+
+        let evicted = if self.entries.len() >= self.capacity {
+            self.evict()
+        } else {
+            None
+        };
+        let bucket = match self.buckets.front_node() {
+            Some(front) if unsafe { self.buckets.get(front) }.freq == 1 => front,
+            _ => self.buckets.push_front_node(Bucket::new(1)),
+        };
+        unsafe { self.buckets.get_mut(bucket) }
+            .keys
+            .put(key.clone(), ());
+        self.entries.insert(key, (value, bucket));
+        evicted
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.touch(key);
+        self.entries.get(key).map(|(v, _)| v)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|(v, _)| v)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let (value, bucket) = self.entries.remove(key)?;
+        self.unlink(key, bucket);
+        Some(value)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.buckets.clear();
+    }
+}
+
+#[test]
+fn test_lfu() {
+    let mut cache = LFUCache::new_with_capacity(3);
+    cache.put(1, 1);
+    cache.put(2, 2);
+    cache.put(3, 3);
+    cache.get(&1);
+    cache.get(&1);
+    cache.get(&2);
+    assert_eq!(cache.frequency(&1), Some(3));
+
+    // 3只访问过一次
+    assert_eq!(cache.put(4, 4), Some((3, 3)));
+    // 4次数最少
+    assert_eq!(cache.put(5, 5), Some((4, 4)));
+    cache.get(&5);
+    // 2和5同为2次，2更久未使用
+    assert_eq!(cache.put(6, 6), Some((2, 2)));
+
+    assert_eq!(cache.remove(&6), Some(6));
+    assert_eq!(cache.len(), 2);
+    assert!(cache.put(7, 7).is_none());
+    assert_eq!(cache.frequency(&7), Some(1));
+    assert_eq!(cache.put(8, 8), Some((7, 7)));
+
+    // 次数不连续时在中间新建桶
+    let mut cache = LFUCache::new_with_capacity(2);
+    cache.put(1, 1);
+    cache.get(&1);
+    cache.get(&1);
+    cache.put(2, 2);
+    cache.get(&2);
+    assert_eq!(cache.frequency(&2), Some(2));
+    cache.remove(&2);
+    cache.put(3, 3);
+    for _ in 0..3 {
+        cache.get(&3);
+    }
+    assert_eq!(cache.frequency(&3), Some(4));
+    assert_eq!(cache.put(4, 4), Some((1, 1)));
+}
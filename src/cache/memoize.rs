@@ -0,0 +1,146 @@
+use std::hash::Hash;
+
+use crate::sort::lru::LRUCache;
+use crate::sort::lru_lockfree::{CacheError, LRUCacheHandle};
+
+/// 缓存函数结果，f应当是纯函数，同样的参数总是得到同样的结果
+pub struct Memoize<F, K, V> {
+    f: F,
+    cache: LRUCache<K, V>,
+}
+
+impl<F, K, V> Memoize<F, K, V>
+where
+    F: FnMut(&K) -> V,
+    K: Hash + Eq,
+    V: Clone,
+{
+    pub fn new_with_capacity(f: F, capacity: usize) -> Self {
+        Self {
+            f,
+            cache: LRUCache::new_with_capacity(capacity),
+        }
+    }
+
+    /// 命中时直接返回缓存的结果，否则调用f并缓存
+    pub fn call(&mut self, key: K) -> V {
+        if let Some(v) = self.cache.get(&key) {
+            return v;
+        }
+        let v = (self.f)(&key);
+        self.cache.put(key, v.clone());
+        v
+    }
+
+    /// 可用于查看命中统计或清空
+    pub fn cache(&self) -> &LRUCache<K, V> {
+        &self.cache
+    }
+
+    pub fn cache_mut(&mut self) -> &mut LRUCache<K, V> {
+        &mut self.cache
+    }
+}
+
+/// 线程安全版，结果缓存在LRUCacheHandle中，可以放入Arc在多个线程间共享
+pub struct SharedMemoize<F, K, V> {
+    f: F,
+    handle: LRUCacheHandle<K, V>,
+}
+
+impl<F, K, V> SharedMemoize<F, K, V>
+where
+    F: Fn(&K) -> V,
+    K: Hash + Eq + Clone + std::fmt::Debug + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    pub fn new(f: F, capacity: usize) -> Self {
+        Self::from_handle(f, LRUCacheHandle::new(capacity))
+    }
+
+    /// 使用已创建的handle，例如有界队列或tokio task版本
+    pub fn from_handle(f: F, handle: LRUCacheHandle<K, V>) -> Self {
+        Self { f, handle }
+    }
+
+    /// 同一参数并发未命中时只计算一次
+    pub async fn call(&self, key: K) -> Result<V, CacheError> {
+        let arg = key.clone();
+        self.handle
+            .get_or_load(key, || async move { (self.f)(&arg) })
+            .await
+    }
+
+    /// 同步版本，不能在tokio runtime内调用。并发未命中时可能重复计算
+    pub fn blocking_call(&self, key: K) -> Result<V, CacheError> {
+        if let Some(v) = self.handle.get(key.clone())? {
+            return Ok(v);
+        }
+        let v = (self.f)(&key);
+        self.handle.put(key, v.clone())?;
+        Ok(v)
+    }
+
+    pub fn handle(&self) -> &LRUCacheHandle<K, V> {
+        &self.handle
+    }
+}
+
+#[test]
+fn test_memoize() {
+    let mut calls = 0;
+    let mut square = Memoize::new_with_capacity(
+        |x: &u64| {
+            calls += 1;
+            x * x
+        },
+        2,
+    );
+    square.cache_mut().enable_stats();
+    assert_eq!(square.call(3), 9);
+    assert_eq!(square.call(3), 9);
+    assert_eq!(square.call(4), 16);
+    // 3最久未使用，被淘汰后重新计算
+    assert_eq!(square.call(5), 25);
+    assert_eq!(square.call(3), 9);
+    assert_eq!(square.cache().stats().unwrap().hits, 1);
+    drop(square);
+    assert_eq!(calls, 4);
+}
+
+#[test]
+fn test_shared_memoize() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let len = Arc::new(SharedMemoize::new(
+        move |s: &String| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            s.len()
+        },
+        10,
+    ));
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let len = len.clone();
+            std::thread::spawn(move || len.blocking_call("abc".to_owned()).unwrap())
+        })
+        .collect();
+    for t in threads {
+        assert_eq!(t.join().unwrap(), 3);
+    }
+    let after_threads = calls.load(Ordering::Relaxed);
+    assert!((1..=4).contains(&after_threads));
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        assert_eq!(len.call("abc".to_owned()).await, Ok(3));
+        assert_eq!(len.call("hello".to_owned()).await, Ok(5));
+    });
+    assert_eq!(calls.load(Ordering::Relaxed), after_threads + 1);
+}
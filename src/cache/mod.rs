@@ -0,0 +1,110 @@
+//! 缓存淘汰策略，统一实现[`Cache`]，调用方只依赖trait即可切换策略
+pub mod arc;
+pub mod lfu;
+pub mod memoize;
+pub mod store;
+pub mod tiny_lfu;
+pub mod two_queue;
+
+use std::hash::Hash;
+
+use crate::sort::lru::LRUCache;
+
+pub use arc::ARCCache;
+pub use lfu::LFUCache;
+pub use memoize::{Memoize, SharedMemoize};
+pub use store::{CachedStore, Store, WriteMode};
+pub use tiny_lfu::TinyLFUCache;
+pub use two_queue::TwoQueueCache;
+
+pub trait Cache<K, V> {
+    /// 插入或覆盖，返回因此被淘汰的元素
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)>;
+
+    /// 查询，命中时按各自策略记录一次访问
+    fn get(&mut self, key: &K) -> Option<&V>;
+
+    /// 查询但不记录访问
+    fn peek(&self, key: &K) -> Option<&V>;
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn capacity(&self) -> usize;
+
+    fn clear(&mut self);
+}
+
+impl<K: Hash + Eq, V> Cache<K, V> for LRUCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        LRUCache::put(self, key, value)
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.get_ref(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        LRUCache::peek(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        LRUCache::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        LRUCache::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        LRUCache::capacity(self)
+    }
+
+    fn clear(&mut self) {
+        LRUCache::clear(self)
+    }
+}
+
+#[test]
+fn test_cache_trait() {
+    // 调用方只依赖trait
+    fn run(cache: &mut dyn Cache<u32, String>) {
+        assert!(cache.is_empty());
+        for i in 0..4 {
+            assert!(cache.put(i, i.to_string()).is_none());
+        }
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.get(&1).unwrap(), "1");
+        assert_eq!(cache.peek(&2).unwrap(), "2");
+        assert!(cache.put(1, "one".to_owned()).is_none());
+        assert_eq!(cache.get(&1).unwrap(), "one");
+
+        // 超出容量淘汰一个
+        let (k, v) = cache.put(4, "4".to_owned()).unwrap();
+        assert_eq!(v, k.to_string());
+        assert!(!cache.contains_key(&k));
+        assert_eq!(cache.len(), 4);
+
+        assert_eq!(cache.remove(&1).unwrap(), "one");
+        assert!(cache.remove(&1).is_none());
+        assert_eq!(cache.len(), 3);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.capacity(), 4);
+    }
+
+    run(&mut LRUCache::new_with_capacity(4));
+    run(&mut LFUCache::new_with_capacity(4));
+    run(&mut TwoQueueCache::new_with_capacity(4));
+    run(&mut ARCCache::new_with_capacity(4));
+    run(&mut TinyLFUCache::new_with_capacity(4));
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use crate::sort::lru::{EvictionReason, LRUCache};
+
+/// 缓存背后的慢速存储
+pub trait Store<K, V> {
+    type Error;
+
+    fn load(&mut self, key: &K) -> Result<Option<V>, Self::Error>;
+
+    fn store(&mut self, key: &K, value: &V) -> Result<(), Self::Error>;
+
+    fn delete(&mut self, key: &K) -> Result<(), Self::Error>;
+}
+
+/// 内存存储，主要用于测试
+impl<K: Hash + Eq + Clone, V: Clone> Store<K, V> for HashMap<K, V> {
+    type Error = Infallible;
+
+    fn load(&mut self, key: &K) -> Result<Option<V>, Self::Error> {
+        Ok(self.get(key).cloned())
+    }
+
+    fn store(&mut self, key: &K, value: &V) -> Result<(), Self::Error> {
+        self.insert(key.clone(), value.clone());
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &K) -> Result<(), Self::Error> {
+        self.remove(key);
+        Ok(())
+    }
+}
+
+/// 写入策略，读取都是read-through：未命中时从存储加载并放入缓存
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// 只写存储，缓存中的旧值失效，下次读取时重新加载
+    ReadThrough,
+    /// 同时写存储和缓存
+    WriteThrough,
+    /// 只写缓存并标记为脏，被淘汰或`flush`时才写回存储
+    WriteBack,
+}
+
+struct Entry<V> {
+    value: V,
+    dirty: bool,
+}
+
+// 被淘汰的元素，由淘汰回调放入，之后写回存储
+type Evicted<K, V> = Arc<Mutex<Vec<(K, Entry<V>)>>>;
+
+/// 以LRUCache作为存储前的缓存层。
+/// WriteBack模式下drop前需调用`flush`或`into_store`，否则未写回的脏数据会丢失。
+/// 淘汰数据写回失败不影响`get`和`put`本身的结果，会在之后重试，由`flush`报告错误
+pub struct CachedStore<K, V, S> {
+    cache: LRUCache<K, Entry<V>>,
+    store: S,
+    mode: WriteMode,
+    evicted: Evicted<K, V>,
+}
+
+impl<K, V, S> CachedStore<K, V, S>
+where
+    K: Hash + Eq + Send + 'static,
+    V: Clone + Send + 'static,
+    S: Store<K, V>,
+{
+    pub fn new(store: S, capacity: usize, mode: WriteMode) -> Self {
+        let evicted = Evicted::default();
+        let mut cache = LRUCache::new_with_capacity(capacity);
+        let queue = evicted.clone();
+        cache.on_evict(move |k, entry: Entry<V>, reason| {
+            // 覆盖的旧值已被新值取代，不需要写回
+            if entry.dirty && reason != EvictionReason::Replaced {
+                queue.lock().unwrap().push((k, entry));
+            }
+        });
+        Self {
+            cache,
+            store,
+            mode,
+            evicted,
+        }
+    }
+
+    pub fn mode(&self) -> WriteMode {
+        self.mode
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// 未写回存储的元素个数，包括已淘汰但写回失败的
+    pub fn dirty_len(&self) -> usize {
+        self.cache.values().filter(|e| e.dirty).count() + self.evicted.lock().unwrap().len()
+    }
+
+    /// 未命中时从存储加载
+    pub fn get(&mut self, key: &K) -> Result<Option<V>, S::Error>
+    where
+        K: Clone,
+    {
+        if let Some(entry) = self.cache.get_ref(key) {
+            return Ok(Some(entry.value.clone()));
+        }
+        // 已淘汰但写回失败的值比存储中的新
+        let queued = self.evicted.lock().unwrap();
+        if let Some((_, entry)) = queued.iter().rev().find(|(k, _)| k == key) {
+            return Ok(Some(entry.value.clone()));
+        }
+        drop(queued);
+        let Some(value) = self.store.load(key)? else {
+            return Ok(None);
+        };
+        let entry = Entry {
+            value: value.clone(),
+            dirty: false,
+        };
+        self.cache.put(key.clone(), entry);
+        self.retry_evicted();
+        Ok(Some(value))
+    }
+
+    pub fn put(&mut self, key: K, value: V) -> Result<(), S::Error> {
+        // 待写回的旧值已被取代，留着会在之后覆盖新值
+        self.evicted.lock().unwrap().retain(|(k, _)| k != &key);
+        match self.mode {
+            WriteMode::ReadThrough => {
+                self.store.store(&key, &value)?;
+                self.cache.remove(&key);
+            }
+            WriteMode::WriteThrough => {
+                self.store.store(&key, &value)?;
+                let entry = Entry {
+                    value,
+                    dirty: false,
+                };
+                self.cache.put(key, entry);
+            }
+            WriteMode::WriteBack => {
+                let entry = Entry { value, dirty: true };
+                self.cache.put(key, entry);
+            }
+        }
+        self.retry_evicted();
+        Ok(())
+    }
+
+    /// 从缓存和存储中删除
+    pub fn remove(&mut self, key: &K) -> Result<(), S::Error> {
+        self.cache.remove(key);
+        // 已淘汰待写回的旧值也不再写回
+        self.evicted.lock().unwrap().retain(|(k, _)| k != key);
+        self.store.delete(key)
+    }
+
+    /// 把所有脏数据写回存储，包括之前写回失败的淘汰数据
+    pub fn flush(&mut self) -> Result<(), S::Error> {
+        self.write_evicted()?;
+        for (k, entry) in self.cache.iter_mut() {
+            if entry.dirty {
+                self.store.store(k, &entry.value)?;
+                entry.dirty = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// 写回脏数据后取出存储
+    pub fn into_store(mut self) -> Result<S, S::Error> {
+        self.flush()?;
+        Ok(self.store)
+    }
+
+    // 顺带写回被淘汰的脏数据，失败时留在队列中，由dirty_len计入、flush报告
+    fn retry_evicted(&mut self) {
+        let _ = self.write_evicted();
+    }
+
+    // 写回被淘汰的脏数据，失败时未写回的留在队列中，下次重试
+    fn write_evicted(&mut self) -> Result<(), S::Error> {
+        let mut evicted = self.evicted.lock().unwrap();
+        while let Some((k, entry)) = evicted.pop() {
+            if let Err(e) = self.store.store(&k, &entry.value) {
+                evicted.push((k, entry));
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_cached_store() {
+    let backing: HashMap<u32, String> = (0..10).map(|i| (i, i.to_string())).collect();
+
+    // 读取未命中时加载
+    let mut cached = CachedStore::new(backing.clone(), 2, WriteMode::ReadThrough);
+    assert_eq!(cached.get(&1).unwrap().as_deref(), Some("1"));
+    assert_eq!(cached.get(&100).unwrap(), None);
+    assert_eq!(cached.len(), 1);
+    // 写入使缓存失效
+    cached.put(1, "one".to_owned()).unwrap();
+    assert!(cached.is_empty());
+    assert_eq!(cached.get(&1).unwrap().as_deref(), Some("one"));
+    cached.remove(&1).unwrap();
+    assert_eq!(cached.get(&1).unwrap(), None);
+
+    let mut cached = CachedStore::new(backing.clone(), 2, WriteMode::WriteThrough);
+    cached.put(1, "one".to_owned()).unwrap();
+    assert_eq!(cached.store()[&1], "one");
+    assert_eq!(cached.len(), 1);
+}
+
+#[test]
+fn test_cached_store_write_back() {
+    let mut cached = CachedStore::new(HashMap::new(), 2, WriteMode::WriteBack);
+    cached.put(1, 1).unwrap();
+    cached.put(2, 2).unwrap();
+    cached.put(1, 10).unwrap();
+    // 写回前存储中没有
+    assert!(cached.store().is_empty());
+    assert_eq!(cached.dirty_len(), 2);
+
+    // 2被淘汰时写回
+    cached.put(3, 3).unwrap();
+    assert_eq!(cached.store(), &HashMap::from([(2, 2)]));
+    assert_eq!(cached.get(&2).unwrap(), Some(2));
+
+    let store = cached.into_store().unwrap();
+    assert_eq!(store, HashMap::from([(1, 10), (2, 2), (3, 3)]));
+}
+
+#[test]
+fn test_cached_store_write_back_failed() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // 可以模拟写入失败的存储
+    struct Flaky {
+        data: HashMap<u32, u32>,
+        down: Arc<AtomicBool>,
+    }
+
+    impl Store<u32, u32> for Flaky {
+        type Error = ();
+
+        fn load(&mut self, key: &u32) -> Result<Option<u32>, ()> {
+            Ok(self.data.get(key).copied())
+        }
+
+        fn store(&mut self, key: &u32, value: &u32) -> Result<(), ()> {
+            if self.down.load(Ordering::Relaxed) {
+                return Err(());
+            }
+            self.data.insert(*key, *value);
+            Ok(())
+        }
+
+        fn delete(&mut self, key: &u32) -> Result<(), ()> {
+            self.data.remove(key);
+            Ok(())
+        }
+    }
+
+    let down = Arc::new(AtomicBool::new(false));
+    let store = Flaky {
+        data: HashMap::from([(1, 100), (3, 300)]),
+        down: down.clone(),
+    };
+    let mut cached = CachedStore::new(store, 1, WriteMode::WriteBack);
+    cached.put(1, 111).unwrap();
+    down.store(true, Ordering::Relaxed);
+    // 1被淘汰但写回失败，不影响本次写入，读取时仍得到未写回的新值
+    cached.put(2, 2).unwrap();
+    assert_eq!(cached.get(&1), Ok(Some(111)));
+    assert_eq!(cached.get(&1), Ok(Some(111)));
+    assert_eq!(cached.dirty_len(), 2);
+    // 其他key的写回失败不影响读取，2被淘汰后也等待写回
+    assert_eq!(cached.get(&3), Ok(Some(300)));
+    assert_eq!(cached.dirty_len(), 2);
+    assert_eq!(cached.flush(), Err(()));
+
+    // 覆盖后不再写回旧值
+    cached.put(1, 1).unwrap();
+    down.store(false, Ordering::Relaxed);
+    let store = cached.into_store().unwrap();
+    assert_eq!(store.data, HashMap::from([(1, 1), (2, 2), (3, 300)]));
+}
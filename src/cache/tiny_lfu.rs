@@ -0,0 +1,252 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+
+use super::Cache;
+use crate::sort::lru::LRUCache;
+
+/// Window TinyLFU
+///
+/// * 新元素先进入占容量1%的窗口LRU
+/// * 被窗口淘汰的候选元素与主缓存的淘汰对象比较访问频率（count-min sketch估算），频率高者留下
+/// * 主缓存为分段LRU：新进入的在probation，再次命中升入protected（占主缓存80%）
+pub struct TinyLFUCache<K, V> {
+    window: LRUCache<K, V>,
+    probation: LRUCache<K, V>,
+    protected: LRUCache<K, V>,
+    sketch: CountMinSketch,
+    main_capacity: usize,
+    protected_capacity: usize,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> TinyLFUCache<K, V> {
+    pub fn new_with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        let window_capacity = (capacity / 100).max(1);
+        let main_capacity = capacity - window_capacity;
+        Self {
+            window: LRUCache::new_with_capacity(window_capacity),
+            probation: LRUCache::unbounded(),
+            protected: LRUCache::unbounded(),
+            sketch: CountMinSketch::new(capacity),
+            main_capacity,
+            protected_capacity: main_capacity * 4 / 5,
+            capacity,
+        }
+    }
+
+    // probation中命中，升入protected，protected超出时把其最久未使用的降回probation
+    fn promote(&mut self, key: &K) {
+        if let Some(v) = self.probation.remove(key) {
+            self.protected.put(key.clone(), v);
+            if self.protected.len() > self.protected_capacity {
+                if let Some((k, v)) = self.protected.pop_lru() {
+                    self.probation.put(k, v);
+                }
+            }
+        }
+    }
+
+    // 窗口淘汰的候选者与主缓存的淘汰对象比较频率
+    fn admit(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if self.probation.len() + self.protected.len() < self.main_capacity {
+            self.probation.put(key, value);
+            return None;
+        }
+        let victim = if self.probation.is_empty() {
+            &mut self.protected
+        } else {
+            &mut self.probation
+        };
+        let Some((victim_key, _)) = victim.peek_lru() else {
+            // 主缓存容量为0
+            return Some((key, value));
+        };
+        if self.sketch.estimate(&key) > self.sketch.estimate(victim_key) {
+            let evicted = victim.pop_lru();
+            self.probation.put(key, value);
+            evicted
+        } else {
+            Some((key, value))
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for TinyLFUCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.sketch.increment(&key);
+        if self.window.contains_key(&key) {
+            self.window.put(key, value);
+            return None;
+        }
+        if self.protected.contains_key(&key) {
+            self.protected.put(key, value);
+            return None;
+        }
+        if let Some(v) = self.probation.get_mut(&key) {
+            *v = value;
+            self.promote(&key);
+            return None;
+        }
+
+        let (k, v) = self.window.put(key, value)?;
+        self.admit(k, v)
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        // 未命中也计入频率
+        self.sketch.increment(key);
+        if self.window.contains_key(key) {
+            return self.window.get_ref(key);
+        }
+        if self.probation.contains_key(key) {
+            self.promote(key);
+        }
+        self.protected
+            .get_ref(key)
+            .or_else(|| self.probation.peek(key))
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.window
+            .peek(key)
+            .or_else(|| self.probation.peek(key))
+            .or_else(|| self.protected.peek(key))
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.window
+            .remove(key)
+            .or_else(|| self.probation.remove(key))
+            .or_else(|| self.protected.remove(key))
+    }
+
+    fn len(&self) -> usize {
+        self.window.len() + self.probation.len() + self.protected.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn clear(&mut self) {
+        self.window.clear();
+        self.probation.clear();
+        self.protected.clear();
+        self.sketch.clear();
+    }
+}
+
+const DEPTH: usize = 4;
+// 计数上限，同4bit计数器
+const MAX_COUNT: u8 = 15;
+
+/// 4行的count-min sketch，估算访问频率，取各行计数的最小值。
+/// 累计次数达到`10 * width`后所有计数减半，使旧的热点逐渐冷却
+pub struct CountMinSketch {
+    table: Vec<[u8; DEPTH]>,
+    // width - 1，width为2的幂
+    mask: usize,
+    additions: usize,
+    sample_size: usize,
+    hasher: RandomState,
+}
+
+impl CountMinSketch {
+    pub fn new(capacity: usize) -> Self {
+        let width = capacity.next_power_of_two().max(16);
+        Self {
+            table: vec![[0; DEPTH]; width],
+            mask: width - 1,
+            additions: 0,
+            sample_size: 10 * width,
+            hasher: RandomState::new(),
+        }
+    }
+
+    // 每行用不同的种子从同一个hash派生下标
+    fn indexes<T: Hash + ?Sized>(&self, item: &T) -> [usize; DEPTH] {
+        const SEEDS: [u64; DEPTH] = [
+            0xc3a5_c85c_97cb_3127,
+            0xb492_b66f_be98_f273,
+            0x9ae1_6a3b_2f90_404f,
+            0xcbf2_9ce4_8422_2325,
+        ];
+        let hash = self.hasher.hash_one(item);
+        SEEDS.map(|seed| {
+            let h = (hash ^ seed).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            (h >> 32) as usize & self.mask
+        })
+    }
+
+    pub fn increment<T: Hash + ?Sized>(&mut self, item: &T) {
+        for (row, idx) in self.indexes(item).into_iter().enumerate() {
+            let count = &mut self.table[idx][row];
+            *count = (*count + 1).min(MAX_COUNT);
+        }
+        self.additions += 1;
+        if self.additions >= self.sample_size {
+            self.reset();
+        }
+    }
+
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u8 {
+        self.indexes(item)
+            .into_iter()
+            .enumerate()
+            .map(|(row, idx)| self.table[idx][row])
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        self.table
+            .iter_mut()
+            .for_each(|counts| *counts = [0; DEPTH]);
+        self.additions = 0;
+    }
+
+    fn reset(&mut self) {
+        for counts in self.table.iter_mut() {
+            counts.iter_mut().for_each(|c| *c >>= 1);
+        }
+        self.additions /= 2;
+    }
+}
+
+#[test]
+fn test_count_min_sketch() {
+    let mut sketch = CountMinSketch::new(16);
+    for _ in 0..5 {
+        sketch.increment(&1);
+    }
+    sketch.increment(&2);
+    // 只会因冲突高估，不会低估
+    assert!(sketch.estimate(&1) >= 5);
+    assert!(sketch.estimate(&2) >= 1);
+    for _ in 0..100 {
+        sketch.increment(&1);
+    }
+    assert_eq!(sketch.estimate(&1), MAX_COUNT);
+}
+
+#[test]
+fn test_tiny_lfu() {
+    let mut cache = TinyLFUCache::new_with_capacity(100);
+    // 热点数据
+    for _ in 0..3 {
+        for i in 0..50 {
+            if cache.get(&i).is_none() {
+                cache.put(i, i);
+            }
+        }
+    }
+    // 再次命中的热点已升入protected，49一直在窗口中未能升级
+    assert_eq!(cache.protected.len(), 49);
+    // 只访问一次的扫描数据无法挤掉热点
+    for i in 1000..2000 {
+        cache.put(i, i);
+    }
+    assert!((0..49).all(|i| cache.peek(&i).is_some()));
+    assert_eq!(cache.len(), 100);
+}
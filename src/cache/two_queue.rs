@@ -0,0 +1,128 @@
+use std::hash::Hash;
+
+use super::Cache;
+use crate::sort::lru::LRUCache;
+
+/// 2Q，新元素先进入FIFO队列A1in，被淘汰后key记入幽灵队列A1out，
+/// 在A1out中再次命中才进入LRU队列Am。只访问一次的扫描数据不会挤掉Am中的热点
+pub struct TwoQueueCache<K, V> {
+    // FIFO，命中不调整顺序
+    a1in: LRUCache<K, V>,
+    // 只记录key
+    a1out: LRUCache<K, ()>,
+    am: LRUCache<K, V>,
+    // A1in的目标大小
+    kin: usize,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> TwoQueueCache<K, V> {
+    /// A1in占容量的1/4，A1out记录容量1/2个key
+    pub fn new_with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            a1in: LRUCache::unbounded(),
+            a1out: LRUCache::new_with_capacity((capacity / 2).max(1)),
+            am: LRUCache::unbounded(),
+            kin: (capacity / 4).max(1),
+            capacity,
+        }
+    }
+
+    // 缓存已满时腾出一个位置，A1in超出目标大小时优先淘汰A1in
+    fn reclaim(&mut self) -> Option<(K, V)> {
+        if self.len() < self.capacity {
+            return None;
+        }
+        if self.a1in.len() > self.kin || self.am.is_empty() {
+            let (k, v) = self.a1in.pop_lru()?;
+            self.a1out.put(k.clone(), ());
+            Some((k, v))
+        } else {
+            self.am.pop_lru()
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for TwoQueueCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if self.am.contains_key(&key) {
+            self.am.put(key, value);
+            return None;
+        }
+        // 原地替换，不改变A1in的FIFO顺序
+        if let Some(v) = self.a1in.peek_mut(&key) {
+            *v = value;
+            return None;
+        }
+
+        let evicted = self.reclaim();
+        if self.a1out.remove(&key).is_some() {
+            self.am.put(key, value);
+        } else {
+            self.a1in.put(key, value);
+        }
+        evicted
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        if self.am.contains_key(key) {
+            self.am.get_ref(key)
+        } else {
+            self.a1in.peek(key)
+        }
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.am.peek(key).or_else(|| self.a1in.peek(key))
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.am.remove(key).or_else(|| self.a1in.remove(key))
+    }
+
+    fn len(&self) -> usize {
+        self.a1in.len() + self.am.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn clear(&mut self) {
+        self.a1in.clear();
+        self.a1out.clear();
+        self.am.clear();
+    }
+}
+
+#[test]
+fn test_two_queue() {
+    let mut cache = TwoQueueCache::new_with_capacity(8);
+    for i in 0..8 {
+        cache.put(i, i);
+    }
+    // 0、1被淘汰进A1out，再次写入后进入Am
+    cache.put(100, 100);
+    cache.put(101, 101);
+    cache.put(0, 0);
+    cache.put(1, 1);
+    assert!(cache.am.contains_key(&0));
+    assert!(cache.am.contains_key(&1));
+
+    // 大量只访问一次的扫描数据只在A1in内轮转
+    for i in 1000..1100 {
+        cache.put(i, i);
+    }
+    assert_eq!(cache.get(&0), Some(&0));
+    assert_eq!(cache.get(&1), Some(&1));
+    assert_eq!(cache.len(), 8);
+
+    // 更新A1in中的元素不改变先进先出的顺序
+    let mut cache = TwoQueueCache::new_with_capacity(4);
+    for i in 0..4 {
+        cache.put(i, i);
+    }
+    cache.put(0, 10);
+    assert_eq!(cache.put(4, 4), Some((0, 10)));
+}
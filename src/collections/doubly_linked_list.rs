@@ -0,0 +1,708 @@
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// 双向链表，节点单独`Box`分配，首尾增删O(1)，通过[`CursorMut`]可在任意位置O(1)增删。
+///
+/// 另提供节点句柄[`NodeHandle`]，供LRU这类在外部索引节点的结构直接摘下、移动节点
+pub struct DoublyLinkedList<T> {
+    head: Option<NonNull<Node<T>>>,
+    tail: Option<NonNull<Node<T>>>,
+    len: usize,
+    // 告知drop check本结构拥有Node
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+struct Node<T> {
+    value: T,
+    prev: Option<NonNull<Node<T>>>,
+    next: Option<NonNull<Node<T>>>,
+}
+
+/// 指向链表中某个节点，节点被移除后失效。只能用于创建它的链表
+pub struct NodeHandle<T> {
+    node: NonNull<Node<T>>,
+}
+
+impl<T> NodeHandle<T> {
+    /// 指向节点中的值，用裸指针取址，不产生新的引用
+    ///
+    /// # Safety
+    /// 节点必须尚未移除，对已释放的节点取字段地址也是未定义行为
+    pub unsafe fn as_ptr(self) -> *mut T {
+        &raw mut (*self.node.as_ptr()).value
+    }
+}
+
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeHandle<T> {}
+
+impl<T> PartialEq for NodeHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T> Eq for NodeHandle<T> {}
+
+impl<T> fmt::Debug for NodeHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NodeHandle").field(&self.node).finish()
+    }
+}
+
+// SAFETY: 节点只归链表所有，与Box<Node>同等对待
+unsafe impl<T: Send> Send for DoublyLinkedList<T> {}
+unsafe impl<T: Sync> Sync for DoublyLinkedList<T> {}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DoublyLinkedList<T> {
+    pub const fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        // SAFETY: head指向存活的节点，生命周期受&self约束
+        self.head.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: 同front，受&mut self约束
+        self.head.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        // SAFETY: 同front
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: 同front_mut
+        self.tail.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.push_front_node(value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.push_back_node(value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        // SAFETY: head属于本链表
+        self.head.map(|node| unsafe { self.unlink(node) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        // SAFETY: tail属于本链表
+        self.tail.map(|node| unsafe { self.unlink(node) })
+    }
+
+    /// 把other的所有元素移到尾部，other变为空，O(1)
+    pub fn append(&mut self, other: &mut Self) {
+        let Some(other_head) = other.head.take() else {
+            return;
+        };
+        match self.tail {
+            // SAFETY: 两端节点都存活，连接后节点归self所有
+            Some(tail) => unsafe {
+                (*tail.as_ptr()).next = Some(other_head);
+                (*other_head.as_ptr()).prev = Some(tail);
+            },
+            None => self.head = Some(other_head),
+        }
+        self.tail = other.tail.take();
+        self.len += std::mem::take(&mut other.len);
+    }
+
+    /// 在at处一分为二，返回[at, len)部分。at > len时panic
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "split_off index out of bounds");
+        if at == 0 {
+            return std::mem::take(self);
+        }
+        if at == self.len {
+            return Self::new();
+        }
+        // 从较近的一端找到新链表的第一个节点
+        let node = if at <= self.len / 2 {
+            let mut p = self.head.unwrap();
+            for _ in 0..at {
+                // SAFETY: at < len，中途不会走到尾部之后
+                p = unsafe { (*p.as_ptr()).next.unwrap() };
+            }
+            p
+        } else {
+            let mut p = self.tail.unwrap();
+            for _ in at..self.len - 1 {
+                // SAFETY: 同上
+                p = unsafe { (*p.as_ptr()).prev.unwrap() };
+            }
+            p
+        };
+        // SAFETY: 0 < at < len，node前面一定有节点
+        let prev = unsafe {
+            let prev = (*node.as_ptr()).prev.take().unwrap();
+            (*prev.as_ptr()).next = None;
+            prev
+        };
+        let other = Self {
+            head: Some(node),
+            tail: self.tail,
+            len: self.len - at,
+            marker: PhantomData,
+        };
+        self.tail = Some(prev);
+        self.len = at;
+        other
+    }
+
+    pub fn clear(&mut self) {
+        let mut p = self.head.take();
+        self.tail = None;
+        self.len = 0;
+        while let Some(node) = p {
+            // SAFETY: 链表中每个节点都由Box::leak得到，只在这里释放一次
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            p = node.next;
+        }
+    }
+
+    /// 从头到尾遍历，`rev()`反向
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// 指向首元素的游标，链表为空时指向“幽灵”位置
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail,
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+
+    /// 插入到头部并返回节点句柄
+    pub fn push_front_node(&mut self, value: T) -> NodeHandle<T> {
+        let node = Self::alloc(value);
+        // SAFETY: 新节点不在任何链表中
+        unsafe { self.link(None, self.head, node) };
+        NodeHandle { node }
+    }
+
+    pub fn push_back_node(&mut self, value: T) -> NodeHandle<T> {
+        let node = Self::alloc(value);
+        // SAFETY: 同push_front_node
+        unsafe { self.link(self.tail, None, node) };
+        NodeHandle { node }
+    }
+
+    pub fn front_node(&self) -> Option<NodeHandle<T>> {
+        self.head.map(|node| NodeHandle { node })
+    }
+
+    pub fn back_node(&self) -> Option<NodeHandle<T>> {
+        self.tail.map(|node| NodeHandle { node })
+    }
+
+    /// 句柄对应节点的前一个节点
+    ///
+    /// # Safety
+    /// handle必须指向本链表中尚未移除的节点，下同
+    pub unsafe fn prev_node(&self, handle: NodeHandle<T>) -> Option<NodeHandle<T>> {
+        (*handle.node.as_ptr()).prev.map(|node| NodeHandle { node })
+    }
+
+    /// # Safety
+    /// 同[`prev_node`](Self::prev_node)
+    pub unsafe fn next_node(&self, handle: NodeHandle<T>) -> Option<NodeHandle<T>> {
+        (*handle.node.as_ptr()).next.map(|node| NodeHandle { node })
+    }
+
+    /// # Safety
+    /// 同[`prev_node`](Self::prev_node)
+    pub unsafe fn get(&self, handle: NodeHandle<T>) -> &T {
+        &(*handle.node.as_ptr()).value
+    }
+
+    /// # Safety
+    /// 同[`prev_node`](Self::prev_node)
+    pub unsafe fn get_mut(&mut self, handle: NodeHandle<T>) -> &mut T {
+        &mut (*handle.node.as_ptr()).value
+    }
+
+    /// 插入到handle之后并返回新节点的句柄
+    ///
+    /// # Safety
+    /// 同[`prev_node`](Self::prev_node)
+    pub unsafe fn insert_after_node(&mut self, handle: NodeHandle<T>, value: T) -> NodeHandle<T> {
+        let node = Self::alloc(value);
+        self.link(Some(handle.node), (*handle.node.as_ptr()).next, node);
+        NodeHandle { node }
+    }
+
+    /// 把节点移到头部
+    ///
+    /// # Safety
+    /// 同[`prev_node`](Self::prev_node)
+    pub unsafe fn move_to_front(&mut self, handle: NodeHandle<T>) {
+        let node = handle.node;
+        if self.head == Some(node) {
+            return;
+        }
+        self.detach(node);
+        self.link(None, self.head, node);
+    }
+
+    /// 移除节点并返回其值，之后handle失效
+    ///
+    /// # Safety
+    /// 同[`prev_node`](Self::prev_node)
+    pub unsafe fn remove_node(&mut self, handle: NodeHandle<T>) -> T {
+        self.unlink(handle.node)
+    }
+
+    fn alloc(value: T) -> NonNull<Node<T>> {
+        NonNull::from(Box::leak(Box::new(Node {
+            value,
+            prev: None,
+            next: None,
+        })))
+    }
+
+    // 把不在链表中的node接到prev与next之间，prev/next须相邻，None表示链表端点
+    unsafe fn link(
+        &mut self,
+        prev: Option<NonNull<Node<T>>>,
+        next: Option<NonNull<Node<T>>>,
+        node: NonNull<Node<T>>,
+    ) {
+        (*node.as_ptr()).prev = prev;
+        (*node.as_ptr()).next = next;
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = Some(node),
+            None => self.head = Some(node),
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).prev = Some(node),
+            None => self.tail = Some(node),
+        }
+        self.len += 1;
+    }
+
+    // 从链表中摘下但不释放。修改前节点next，后节点prev，若首尾节点修改head/tail
+    unsafe fn detach(&mut self, node: NonNull<Node<T>>) {
+        let node = node.as_ptr();
+        match (*node).prev {
+            Some(prev) => (*prev.as_ptr()).next = (*node).next,
+            None => self.head = (*node).next,
+        }
+        match (*node).next {
+            Some(next) => (*next.as_ptr()).prev = (*node).prev,
+            None => self.tail = (*node).prev,
+        }
+        (*node).prev = None;
+        (*node).next = None;
+        self.len -= 1;
+    }
+
+    // 摘下并释放节点
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        self.detach(node);
+        Box::from_raw(node.as_ptr()).value
+    }
+}
+
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DoublyLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T> FromIterator<T> for DoublyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for DoublyLinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|v| self.push_back(v));
+    }
+}
+
+pub struct Iter<'a, T> {
+    head: Option<NonNull<Node<T>>>,
+    tail: Option<NonNull<Node<T>>>,
+    // 剩余个数，首尾相遇时停止
+    len: usize,
+    marker: PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| {
+            // SAFETY: 节点在'a内存活且只被共享借用
+            let node = unsafe { &*node.as_ptr() };
+            self.len -= 1;
+            self.head = node.next;
+            &node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| {
+            // SAFETY: 同next
+            let node = unsafe { &*node.as_ptr() };
+            self.len -= 1;
+            self.tail = node.prev;
+            &node.value
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    head: Option<NonNull<Node<T>>>,
+    tail: Option<NonNull<Node<T>>>,
+    len: usize,
+    marker: PhantomData<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| {
+            // SAFETY: 每个节点只被访问一次，只借出value，不与prev/next重叠
+            let node = node.as_ptr();
+            self.len -= 1;
+            unsafe {
+                self.head = (*node).next;
+                &mut (*node).value
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| {
+            // SAFETY: 同next
+            let node = node.as_ptr();
+            self.len -= 1;
+            unsafe {
+                self.tail = (*node).prev;
+                &mut (*node).value
+            }
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+pub struct IntoIter<T> {
+    list: DoublyLinkedList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DoublyLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DoublyLinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// 可修改链表的游标。尾部之后、头部之前有一个不对应元素的“幽灵”位置，
+/// 在幽灵位置move_next回到头部，move_prev回到尾部
+pub struct CursorMut<'a, T> {
+    // None为幽灵位置
+    current: Option<NonNull<Node<T>>>,
+    // 幽灵位置时为len
+    index: usize,
+    list: &'a mut DoublyLinkedList<T>,
+}
+
+impl<T> CursorMut<'_, T> {
+    /// 当前元素的下标，幽灵位置为None
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        // SAFETY: current指向链表中存活的节点，受&mut self约束
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = match self.current {
+            // SAFETY: 同current
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => self.list.head,
+        };
+        // SAFETY: 同current
+        next.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = match self.current {
+            // SAFETY: 同current
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        // SAFETY: 同current
+        prev.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn move_next(&mut self) {
+        match self.current {
+            Some(node) => {
+                // SAFETY: 同current
+                self.current = unsafe { (*node.as_ptr()).next };
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.current {
+            Some(node) => {
+                // SAFETY: 同current
+                self.current = unsafe { (*node.as_ptr()).prev };
+                // 从头部移到幽灵位置时下标为len
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            }
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// 插入到当前元素之前，幽灵位置时插入到尾部
+    pub fn insert_before(&mut self, value: T) {
+        let node = DoublyLinkedList::alloc(value);
+        let prev = match self.current {
+            // SAFETY: 同current
+            Some(cur) => unsafe { (*cur.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        // SAFETY: prev与current相邻，新节点不在链表中
+        unsafe { self.list.link(prev, self.current, node) };
+        self.index += 1;
+    }
+
+    /// 插入到当前元素之后，幽灵位置时插入到头部
+    pub fn insert_after(&mut self, value: T) {
+        let node = DoublyLinkedList::alloc(value);
+        let next = match self.current {
+            // SAFETY: 同current
+            Some(cur) => unsafe { (*cur.as_ptr()).next },
+            None => self.list.head,
+        };
+        // SAFETY: 同insert_before
+        unsafe { self.list.link(self.current, next, node) };
+        if self.current.is_none() {
+            // 幽灵位置的下标随长度变化
+            self.index += 1;
+        }
+    }
+
+    /// 移除当前元素，游标移到下一个元素
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current?;
+        // SAFETY: 同current
+        unsafe {
+            self.current = (*node.as_ptr()).next;
+            Some(self.list.unlink(node))
+        }
+    }
+}
+
+#[test]
+fn test_doubly_linked_list() {
+    let mut list: DoublyLinkedList<i32> = (1..=3).collect();
+    list.push_front(0);
+    list.push_back(4);
+    assert_eq!(list.len(), 5);
+    assert_eq!(format!("{list:?}"), "[0, 1, 2, 3, 4]");
+    assert_eq!(
+        list.iter().rev().copied().collect::<Vec<_>>(),
+        [4, 3, 2, 1, 0]
+    );
+    assert_eq!((list.pop_front(), list.pop_back()), (Some(0), Some(4)));
+    *list.front_mut().unwrap() += 10;
+    assert_eq!((list.front(), list.back()), (Some(&11), Some(&3)));
+
+    let mut other: DoublyLinkedList<_> = (4..8).collect();
+    list.append(&mut other);
+    assert!(other.is_empty());
+    let tail = list.split_off(4);
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), [11, 2, 3, 4]);
+    assert_eq!(tail.into_iter().collect::<Vec<_>>(), [5, 6, 7]);
+    let front = list.split_off(0);
+    assert!(list.is_empty());
+    assert_eq!(front.len(), 4);
+
+    // 节点句柄
+    let mut list = DoublyLinkedList::new();
+    let a = list.push_back_node("a");
+    let b = list.push_back_node("b");
+    list.push_back("c");
+    unsafe {
+        list.move_to_front(b);
+        assert_eq!(list.next_node(b), Some(a));
+        assert_eq!(list.remove_node(a), "a");
+        assert_eq!(*list.get(b), "b");
+    }
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), ["b", "c"]);
+}
+
+#[test]
+fn test_cursor_mut() {
+    let mut list: DoublyLinkedList<i32> = (0..5).collect();
+    let mut cursor = list.cursor_front_mut();
+    cursor.move_next();
+    assert_eq!(cursor.current(), Some(&mut 1));
+    cursor.insert_before(10);
+    cursor.insert_after(11);
+    assert_eq!(cursor.index(), Some(2));
+    assert_eq!(cursor.remove_current(), Some(1));
+    assert_eq!(cursor.current(), Some(&mut 11));
+    assert_eq!(cursor.peek_prev(), Some(&mut 10));
+
+    // 幽灵位置
+    let mut cursor = list.cursor_back_mut();
+    cursor.move_next();
+    assert_eq!(cursor.index(), None);
+    assert_eq!(cursor.peek_next(), Some(&mut 0));
+    cursor.insert_before(5);
+    cursor.insert_after(-1);
+    cursor.move_prev();
+    assert_eq!((cursor.index(), cursor.current()), (Some(7), Some(&mut 5)));
+    assert_eq!(
+        list.into_iter().collect::<Vec<_>>(),
+        [-1, 0, 10, 11, 2, 3, 4, 5]
+    );
+}
@@ -1,57 +1,163 @@
 /// Use pointer `NonNull`
 pub mod unsafe_link_list {
-    use std::fmt::Debug;
+    use std::fmt::{self, Debug};
+    use std::hash::{Hash, Hasher};
     use std::marker::PhantomData;
-    use std::ptr::{drop_in_place, NonNull};
+    use std::ptr::NonNull;
 
-    #[derive(Debug, Default)]
-    pub struct LinkList<T: Debug> {
+    pub struct LinkList<T> {
         head: Option<NonNull<Node<T>>>,
+        len: usize,
     }
 
     #[derive(Debug)]
-    pub struct Node<T: Debug> {
+    pub struct Node<T> {
         data: T,
         next: Option<NonNull<Node<T>>>,
     }
 
-    impl<T: Debug> LinkList<T> {
+    impl<T> LinkList<T> {
+        pub fn new() -> Self {
+            Self { head: None, len: 0 }
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
         pub fn push(&mut self, data: T) {
             let s = Node {
                 data,
-                next: self.head.clone(),
+                next: self.head,
             };
             self.head = Some(Box::leak(Box::new(s)).into());
+            self.len += 1;
         }
 
         pub fn pop(&mut self) -> Option<T> {
             self.head.map(|node| unsafe {
                 let boxed_node = Box::from_raw(node.as_ptr());
                 self.head = boxed_node.next;
+                self.len -= 1;
                 boxed_node.data
             })
         }
 
-        pub fn iter(&self) -> Iter<T> {
+        /// 栈顶元素
+        pub fn peek(&self) -> Option<&T> {
+            // SAFETY: head指向存活的节点，生命周期受&self约束
+            self.head.map(|node| unsafe { &(*node.as_ptr()).data })
+        }
+
+        pub fn peek_mut(&mut self) -> Option<&mut T> {
+            // SAFETY: 同peek，受&mut self约束
+            self.head.map(|node| unsafe { &mut (*node.as_ptr()).data })
+        }
+
+        /// 原地反转，逐个修改next指向前一个节点
+        pub fn reverse(&mut self) {
+            let mut prev = None;
+            let mut p = self.head;
+            while let Some(node) = p {
+                // SAFETY: 链表中的节点都存活，先记下next再修改
+                unsafe {
+                    p = (*node.as_ptr()).next;
+                    (*node.as_ptr()).next = prev;
+                }
+                prev = Some(node);
+            }
+            self.head = prev;
+        }
+
+        pub fn iter(&self) -> Iter<'_, T> {
             Iter {
-                current: self.head.clone(),
+                current: self.head,
+                marker: PhantomData,
+            }
+        }
+
+        pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+            IterMut {
+                current: self.head,
                 marker: PhantomData,
             }
         }
     }
 
-    impl<T: Debug> Drop for LinkList<T> {
-        fn drop(&mut self) {
-            println!("drop list");
-            if let Some(first) = self.head {
-                let mut p_node = first;
-                unsafe {
-                    while let Some(next) = p_node.as_ref().next {
-                        drop_in_place(p_node.as_ptr());
-                        p_node = next;
-                    }
-                    drop_in_place(p_node.as_ptr());
+    impl<T> Default for LinkList<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T: Debug> Debug for LinkList<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    impl<T: Clone> Clone for LinkList<T> {
+        fn clone(&self) -> Self {
+            self.iter().cloned().collect()
+        }
+    }
+
+    impl<T: PartialEq> PartialEq for LinkList<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.len == other.len && self.iter().eq(other.iter())
+        }
+    }
+
+    impl<T: Eq> Eq for LinkList<T> {}
+
+    impl<T: Hash> Hash for LinkList<T> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.len.hash(state);
+            self.iter().for_each(|item| item.hash(state));
+        }
+    }
+
+    impl<T> FromIterator<T> for LinkList<T> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            let mut list = Self::new();
+            list.extend(iter);
+            list
+        }
+    }
+
+    /// 追加到尾部，保持迭代顺序，与`iter()`的顺序一致
+    impl<T> Extend<T> for LinkList<T> {
+        fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+            // 先找到尾节点，之后每个新节点O(1)接在后面
+            let mut tail = self.head;
+            // SAFETY: 链表中的节点都存活
+            while let Some(next) = tail.and_then(|node| unsafe { (*node.as_ptr()).next }) {
+                tail = Some(next);
+            }
+            for data in iter {
+                let node = NonNull::from(Box::leak(Box::new(Node { data, next: None })));
+                match tail {
+                    // SAFETY: tail是存活的尾节点
+                    Some(t) => unsafe { (*t.as_ptr()).next = Some(node) },
+                    None => self.head = Some(node),
                 }
+                tail = Some(node);
+                self.len += 1;
+            }
+        }
+    }
+
+    impl<T> Drop for LinkList<T> {
+        fn drop(&mut self) {
+            let mut p = self.head.take();
+            while let Some(node) = p {
+                // SAFETY: 节点都由Box::leak得到，每个只释放一次
+                let boxed_node = unsafe { Box::from_raw(node.as_ptr()) };
+                p = boxed_node.next;
             }
         }
     }
@@ -64,26 +170,77 @@ pub mod unsafe_link_list {
     //     }
     // }
 
-    pub struct Iter<'a, T: 'a + Debug> {
+    pub struct Iter<'a, T: 'a> {
         current: Option<NonNull<Node<T>>>,
         marker: PhantomData<&'a Node<T>>,
     }
 
-    impl<'a, T: Debug> Iterator for Iter<'a, T> {
+    impl<'a, T> Iterator for Iter<'a, T> {
         type Item = &'a T;
         fn next(&mut self) -> Option<Self::Item> {
             unsafe {
                 let res = self.current.as_ref().map(|m| &m.as_ref().data);
-                self.current = self
-                    .current
-                    .as_ref()
-                    .map(|m| m.as_ref().next.clone())
-                    .flatten();
+                self.current = self.current.and_then(|m| m.as_ref().next);
                 res
             }
         }
     }
 
+    pub struct IterMut<'a, T: 'a> {
+        current: Option<NonNull<Node<T>>>,
+        marker: PhantomData<&'a mut Node<T>>,
+    }
+
+    impl<'a, T> Iterator for IterMut<'a, T> {
+        type Item = &'a mut T;
+        fn next(&mut self) -> Option<Self::Item> {
+            self.current.map(|node| {
+                // SAFETY: 每个节点只被访问一次，只借出data
+                unsafe {
+                    self.current = (*node.as_ptr()).next;
+                    &mut (*node.as_ptr()).data
+                }
+            })
+        }
+    }
+
+    pub struct IntoIter<T>(LinkList<T>);
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.pop()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.0.len, Some(self.0.len))
+        }
+    }
+
+    impl<T> IntoIterator for LinkList<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+        fn into_iter(self) -> Self::IntoIter {
+            IntoIter(self)
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a LinkList<T> {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter()
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a mut LinkList<T> {
+        type Item = &'a mut T;
+        type IntoIter = IterMut<'a, T>;
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter_mut()
+        }
+    }
+
     #[test]
     fn test_link_list() {
         let mut list = LinkList::default();
@@ -103,51 +260,218 @@ pub mod unsafe_link_list {
         assert_eq!(list.pop(), None);
         assert_eq!(list.pop(), None);
     }
+
+    #[test]
+    fn test_link_list_traits() {
+        let mut list: LinkList<i32> = (1..=3).collect();
+        assert_eq!(format!("{list:?}"), "[1, 2, 3]");
+        list.extend([4, 5]);
+        assert_eq!(list.len(), 5);
+        for v in &mut list {
+            *v *= 10;
+        }
+        *list.peek_mut().unwrap() += 1;
+        assert_eq!(list.peek(), Some(&11));
+
+        let copy = list.clone();
+        list.reverse();
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [50, 40, 30, 20, 11]
+        );
+        assert_ne!(list, copy);
+        list.reverse();
+        assert_eq!(list, copy);
+        assert_eq!(copy.into_iter().collect::<Vec<_>>(), [11, 20, 30, 40, 50]);
+
+        // 元素不要求实现Debug
+        let mut fs: LinkList<Box<dyn Fn(i32) -> i32>> = LinkList::new();
+        fs.push(Box::new(|x| x + 1));
+        fs.push(Box::new(|x| x * 2));
+        assert_eq!(fs.iter().fold(3, |acc, f| f(acc)), 7);
+    }
+
+    #[test]
+    fn test_link_list_drop() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct Counted(Rc<Cell<usize>>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(0));
+        let mut list = LinkList::new();
+        for _ in 0..10 {
+            list.push(Counted(dropped.clone()));
+        }
+        drop(list.pop());
+        assert_eq!(dropped.get(), 1);
+        drop(list);
+        assert_eq!(dropped.get(), 10);
+
+        // 长链表不会栈溢出
+        let list: LinkList<u32> = (0..1_000_000).collect();
+        assert_eq!(list.len(), 1_000_000);
+        drop(list);
+    }
 }
 
 /// Use `Box`
 pub mod safe_link_list {
-    use std::fmt::Debug;
+    use std::fmt::{self, Debug};
+    use std::hash::{Hash, Hasher};
 
-    #[derive(Debug, Default)]
-    pub struct LinkList<T: Debug> {
+    pub struct LinkList<T> {
         head: Option<Box<Node<T>>>,
+        len: usize,
     }
 
     #[derive(Debug)]
-    pub struct Node<T: Debug> {
+    pub struct Node<T> {
         data: T,
         next: Option<Box<Node<T>>>,
     }
 
-    impl<T: Debug> LinkList<T> {
+    impl<T> LinkList<T> {
+        pub fn new() -> Self {
+            Self { head: None, len: 0 }
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
         pub fn push(&mut self, data: T) {
             let s = Node {
                 data,
                 next: std::mem::take(&mut self.head),
             };
             self.head = Some(Box::new(s));
+            self.len += 1;
         }
 
         pub fn pop(&mut self) -> Option<T> {
             std::mem::take(&mut self.head).map(|node| {
                 self.head = node.next;
+                self.len -= 1;
                 node.data
             })
         }
 
-        pub fn iter(&self) -> Iter<T> {
+        /// 栈顶元素
+        pub fn peek(&self) -> Option<&T> {
+            self.head.as_ref().map(|node| &node.data)
+        }
+
+        pub fn peek_mut(&mut self) -> Option<&mut T> {
+            self.head.as_mut().map(|node| &mut node.data)
+        }
+
+        /// 原地反转，逐个把节点摘下挂到新链表头部
+        pub fn reverse(&mut self) {
+            let mut reversed = None;
+            let mut p = self.head.take();
+            while let Some(mut node) = p {
+                p = node.next.take();
+                node.next = reversed;
+                reversed = Some(node);
+            }
+            self.head = reversed;
+        }
+
+        pub fn iter(&self) -> Iter<'_, T> {
             Iter {
                 current: &self.head,
             }
         }
+
+        pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+            IterMut {
+                current: self.head.as_deref_mut(),
+            }
+        }
     }
 
-    pub struct Iter<'a, T: 'a + Debug> {
+    impl<T> Default for LinkList<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// 逐个释放节点，默认的递归drop在长链表上会栈溢出
+    impl<T> Drop for LinkList<T> {
+        fn drop(&mut self) {
+            let mut p = self.head.take();
+            while let Some(mut node) = p {
+                p = node.next.take();
+            }
+        }
+    }
+
+    impl<T: Debug> Debug for LinkList<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    impl<T: Clone> Clone for LinkList<T> {
+        fn clone(&self) -> Self {
+            self.iter().cloned().collect()
+        }
+    }
+
+    impl<T: PartialEq> PartialEq for LinkList<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.len == other.len && self.iter().eq(other.iter())
+        }
+    }
+
+    impl<T: Eq> Eq for LinkList<T> {}
+
+    impl<T: Hash> Hash for LinkList<T> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.len.hash(state);
+            self.iter().for_each(|item| item.hash(state));
+        }
+    }
+
+    impl<T> FromIterator<T> for LinkList<T> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            let mut list = Self::new();
+            list.extend(iter);
+            list
+        }
+    }
+
+    /// 追加到尾部，保持迭代顺序，与`iter()`的顺序一致
+    impl<T> Extend<T> for LinkList<T> {
+        fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+            // 指向尾部的空位
+            let mut tail = &mut self.head;
+            while let Some(node) = tail {
+                tail = &mut node.next;
+            }
+            for data in iter {
+                let node = tail.insert(Box::new(Node { data, next: None }));
+                tail = &mut node.next;
+                self.len += 1;
+            }
+        }
+    }
+
+    pub struct Iter<'a, T: 'a> {
         current: &'a Option<Box<Node<T>>>,
     }
 
-    impl<'a, T: Debug> Iterator for Iter<'a, T> {
+    impl<'a, T> Iterator for Iter<'a, T> {
         type Item = &'a T;
         fn next(&mut self) -> Option<Self::Item> {
             if let Some(node) = self.current {
@@ -160,6 +484,57 @@ pub mod safe_link_list {
         }
     }
 
+    pub struct IterMut<'a, T: 'a> {
+        current: Option<&'a mut Node<T>>,
+    }
+
+    impl<'a, T> Iterator for IterMut<'a, T> {
+        type Item = &'a mut T;
+        fn next(&mut self) -> Option<Self::Item> {
+            self.current.take().map(|node| {
+                self.current = node.next.as_deref_mut();
+                &mut node.data
+            })
+        }
+    }
+
+    pub struct IntoIter<T>(LinkList<T>);
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.pop()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.0.len, Some(self.0.len))
+        }
+    }
+
+    impl<T> IntoIterator for LinkList<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+        fn into_iter(self) -> Self::IntoIter {
+            IntoIter(self)
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a LinkList<T> {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter()
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a mut LinkList<T> {
+        type Item = &'a mut T;
+        type IntoIter = IterMut<'a, T>;
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter_mut()
+        }
+    }
+
     #[test]
     fn test_link_list() {
         let mut list = LinkList::default();
@@ -179,4 +554,53 @@ pub mod safe_link_list {
         assert_eq!(list.pop(), None);
         assert_eq!(list.pop(), None);
     }
+    #[test]
+    fn test_link_list_traits() {
+        use std::collections::HashSet;
+
+        let mut list: LinkList<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        list.extend(["c".to_owned()]);
+        assert_eq!(format!("{list:?}"), r#"["a", "b", "c"]"#);
+        for s in &mut list {
+            s.push('!');
+        }
+        list.peek_mut().unwrap().insert(0, '>');
+        assert_eq!(list.peek().unwrap(), ">a!");
+
+        let copy = list.clone();
+        list.reverse();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.iter().next().unwrap(), "c!");
+        let set: HashSet<_> = [list.clone(), copy.clone(), list].into_iter().collect();
+        assert_eq!(set.len(), 2);
+        assert_eq!(copy.into_iter().collect::<Vec<_>>(), [">a!", "b!", "c!"]);
+    }
+
+    #[test]
+    fn test_link_list_drop() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct Counted(Rc<Cell<usize>>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(0));
+        let mut list = LinkList::new();
+        for _ in 0..10 {
+            list.push(Counted(dropped.clone()));
+        }
+        drop(list.pop());
+        assert_eq!(dropped.get(), 1);
+        drop(list);
+        assert_eq!(dropped.get(), 10);
+
+        // 长链表不会栈溢出
+        let list: LinkList<u32> = (0..1_000_000).collect();
+        assert_eq!(list.len(), 1_000_000);
+        drop(list);
+    }
 }
@@ -1,2 +1,5 @@
+pub mod doubly_linked_list;
 pub mod link_list;
+pub mod persistent_list;
+pub mod treiber_stack;
 pub mod vec_array;
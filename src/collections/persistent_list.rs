@@ -0,0 +1,194 @@
+//! 不可变链表，`push`返回共享原链表的新链表，旧版本保持不变。
+//! `PersistentList`基于`Rc`，`ArcPersistentList`基于`Arc`，可以在线程间共享
+use std::fmt::{self, Debug};
+use std::rc::Rc;
+use std::sync::Arc;
+
+macro_rules! persistent_list {
+    ($(#[$attr:meta])* $name:ident, $node:ident, $iter:ident, $rc:ident) => {
+        $(#[$attr])*
+        pub struct $name<T> {
+            head: Option<$rc<$node<T>>>,
+            len: usize,
+        }
+
+        struct $node<T> {
+            data: T,
+            next: Option<$rc<$node<T>>>,
+        }
+
+        impl<T> $name<T> {
+            pub fn new() -> Self {
+                Self { head: None, len: 0 }
+            }
+
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// 返回以data为头、self为尾的新链表，O(1)
+            pub fn push(&self, data: T) -> Self {
+                let node = $node {
+                    data,
+                    next: self.head.clone(),
+                };
+                Self {
+                    head: Some($rc::new(node)),
+                    len: self.len + 1,
+                }
+            }
+
+            pub fn head(&self) -> Option<&T> {
+                self.head.as_ref().map(|node| &node.data)
+            }
+
+            /// 去掉头部后的链表，空链表的tail仍是空链表
+            pub fn tail(&self) -> Self {
+                match &self.head {
+                    Some(node) => Self {
+                        head: node.next.clone(),
+                        len: self.len - 1,
+                    },
+                    None => Self::new(),
+                }
+            }
+
+            pub fn iter(&self) -> $iter<'_, T> {
+                $iter {
+                    current: self.head.as_deref(),
+                }
+            }
+        }
+
+        impl<T> Default for $name<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        /// 只复制头指针，与原链表共享所有节点
+        impl<T> Clone for $name<T> {
+            fn clone(&self) -> Self {
+                Self {
+                    head: self.head.clone(),
+                    len: self.len,
+                }
+            }
+        }
+
+        impl<T: Debug> Debug for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<T: PartialEq> PartialEq for $name<T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.len == other.len && self.iter().eq(other.iter())
+            }
+        }
+
+        impl<T: Eq> Eq for $name<T> {}
+
+        /// 逐个释放不再共享的节点，遇到仍被其他链表引用的节点就停止
+        impl<T> Drop for $name<T> {
+            fn drop(&mut self) {
+                let mut p = self.head.take();
+                // into_inner保证并发drop时恰好有一个线程取得最后的引用，try_unwrap可能都失败
+                while let Some(mut node) = p.and_then($rc::into_inner) {
+                    p = node.next.take();
+                }
+            }
+        }
+
+        pub struct $iter<'a, T> {
+            current: Option<&'a $node<T>>,
+        }
+
+        impl<'a, T> Iterator for $iter<'a, T> {
+            type Item = &'a T;
+            fn next(&mut self) -> Option<Self::Item> {
+                self.current.map(|node| {
+                    self.current = node.next.as_deref();
+                    &node.data
+                })
+            }
+        }
+
+        impl<'a, T> IntoIterator for &'a $name<T> {
+            type Item = &'a T;
+            type IntoIter = $iter<'a, T>;
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
+            }
+        }
+    };
+}
+
+persistent_list!(
+    /// 单线程版本
+    PersistentList,
+    RcNode,
+    Iter,
+    Rc
+);
+
+persistent_list!(
+    /// 线程安全版本，T: Send + Sync时可以在线程间共享
+    ArcPersistentList,
+    ArcNode,
+    ArcIter,
+    Arc
+);
+
+#[test]
+fn test_persistent_list() {
+    let empty = PersistentList::new();
+    let a = empty.push(1);
+    let b = a.push(2).push(3);
+    let c = a.push(4);
+    assert_eq!(format!("{b:?}"), "[3, 2, 1]");
+    assert_eq!(format!("{c:?}"), "[4, 1]");
+    // 旧版本不受影响
+    assert_eq!(a.iter().copied().collect::<Vec<_>>(), [1]);
+    assert!(empty.is_empty());
+
+    assert_eq!(b.head(), Some(&3));
+    assert_eq!(b.tail().tail(), a);
+    assert_eq!(b.len(), 3);
+    assert!(empty.tail().is_empty());
+    assert_eq!(empty.head(), None);
+
+    // 长链表drop不会栈溢出
+    let mut long = PersistentList::new();
+    for i in 0..1_000_000 {
+        long = long.push(i);
+    }
+    let shared = long.tail();
+    drop(long);
+    assert_eq!(shared.head(), Some(&999_998));
+}
+
+#[test]
+fn test_arc_persistent_list() {
+    let base = ArcPersistentList::new()
+        .push("a".to_owned())
+        .push("b".to_owned());
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            let base = base.clone();
+            std::thread::spawn(move || base.push(i.to_string()))
+        })
+        .collect();
+    for (i, t) in threads.into_iter().enumerate() {
+        let list = t.join().unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.head(), Some(&i.to_string()));
+        assert_eq!(list.tail(), base);
+    }
+    assert_eq!(format!("{base:?}"), r#"["b", "a"]"#);
+}
@@ -0,0 +1,160 @@
+//! 无锁栈(Treiber stack)，头指针用CAS更新。
+//! 弹出的节点由crossbeam-epoch延迟回收，避免其他线程读到已释放的节点
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+
+pub struct TreiberStack<T> {
+    head: Atomic<Node<T>>,
+}
+
+struct Node<T> {
+    // 弹出时数据被移出，回收节点时不能再drop
+    data: ManuallyDrop<T>,
+    next: Atomic<Node<T>>,
+}
+
+// 数据只会被弹出它的线程取得，不会共享引用，所以只要求T: Send
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+
+impl<T> TreiberStack<T> {
+    pub fn new() -> Self {
+        Self {
+            head: Atomic::null(),
+        }
+    }
+
+    pub fn push(&self, data: T) {
+        let mut node = Owned::new(Node {
+            data: ManuallyDrop::new(data),
+            next: Atomic::null(),
+        });
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Relaxed, &guard);
+            node.next.store(head, Ordering::Relaxed);
+            match self.head.compare_exchange(
+                head,
+                node,
+                Ordering::Release,
+                Ordering::Relaxed,
+                &guard,
+            ) {
+                Ok(_) => return,
+                // CAS失败时取回节点重试
+                Err(e) => node = e.new,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire, &guard);
+            // SAFETY: 在guard期间节点不会被回收
+            let node = unsafe { head.as_ref() }?;
+            let next = node.next.load(Ordering::Relaxed, &guard);
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::Relaxed, Ordering::Relaxed, &guard)
+                .is_ok()
+            {
+                // SAFETY: CAS成功说明只有当前线程摘下了这个节点，
+                // 数据只移出一次，节点等所有线程离开当前epoch后再释放
+                unsafe {
+                    guard.defer_destroy(head);
+                    return Some(ManuallyDrop::into_inner(ptr::read(&node.data)));
+                }
+            }
+        }
+    }
+
+    /// 并发时结果可能立即过期
+    pub fn is_empty(&self) -> bool {
+        let guard = epoch::pin();
+        self.head.load(Ordering::Acquire, &guard).is_null()
+    }
+}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        // &mut self保证没有其他线程访问，可以直接释放
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut p = self.head.load(Ordering::Relaxed, guard);
+            while !p.is_null() {
+                let mut node = p.into_owned();
+                ManuallyDrop::drop(&mut node.data);
+                p = node.next.load(Ordering::Relaxed, guard);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_treiber_stack() {
+    use std::sync::Arc;
+
+    let stack = TreiberStack::new();
+    assert!(stack.is_empty());
+    stack.push(1);
+    stack.push(2);
+    assert_eq!(stack.pop(), Some(2));
+    assert_eq!(stack.pop(), Some(1));
+    assert_eq!(stack.pop(), None);
+
+    // 剩余元素在drop时释放
+    let item = Arc::new(());
+    let stack = TreiberStack::new();
+    for _ in 0..10 {
+        stack.push(item.clone());
+    }
+    drop(stack.pop());
+    assert_eq!(Arc::strong_count(&item), 10);
+    drop(stack);
+    assert_eq!(Arc::strong_count(&item), 1);
+}
+
+#[test]
+fn test_treiber_stack_stress() {
+    use std::sync::Arc;
+
+    const THREADS: usize = 4;
+    const COUNT: usize = 10_000;
+    let stack = Arc::new(TreiberStack::new());
+    let threads: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let stack = stack.clone();
+            std::thread::spawn(move || {
+                // 交替压入和弹出，弹出的元素留给主线程检查
+                let mut popped = Vec::new();
+                for i in 0..COUNT {
+                    stack.push(t * COUNT + i);
+                    if i % 2 == 0 {
+                        popped.extend(stack.pop());
+                    }
+                }
+                popped
+            })
+        })
+        .collect();
+    let mut all: Vec<usize> = threads
+        .into_iter()
+        .flat_map(|t| t.join().unwrap())
+        .collect();
+    while let Some(v) = stack.pop() {
+        all.push(v);
+    }
+    // 每个元素恰好弹出一次
+    all.sort_unstable();
+    assert_eq!(all, (0..THREADS * COUNT).collect::<Vec<_>>());
+}
@@ -1,5 +1,38 @@
-use std::mem::MaybeUninit;
-use std::ops::Deref;
+use std::fmt;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Bound, Deref, DerefMut, RangeBounds};
+use std::ptr;
+
+/// 空间不足，带回未能放入的元素
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T = ()> {
+    element: T,
+}
+
+impl<T> CapacityError<T> {
+    pub fn new(element: T) -> Self {
+        Self { element }
+    }
+
+    pub fn element(self) -> T {
+        self.element
+    }
+}
+
+// 不要求T: Debug，便于直接unwrap
+impl<T> fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CapacityError: {self}")
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VecArray is full")
+    }
+}
+
+impl<T> std::error::Error for CapacityError<T> {}
 
 pub struct VecArray<T, const N: usize> {
     len: usize,
@@ -10,17 +43,17 @@ impl<T, const N: usize> VecArray<T, N> {
     pub fn new() -> Self {
         VecArray {
             len: 0,
-            array: MaybeUninit::uninit_array(),
+            array: [const { MaybeUninit::uninit() }; N],
         }
     }
 
-    pub fn push(&mut self, data: T) -> Result<(), String> {
+    pub fn push(&mut self, data: T) -> Result<(), CapacityError<T>> {
         if self.len < N {
             self.array[self.len] = MaybeUninit::new(data);
             self.len += 1;
             Ok(())
         } else {
-            Err("VecArray is full".to_owned())
+            Err(CapacityError::new(data))
         }
     }
 
@@ -41,9 +74,7 @@ impl<T, const N: usize> VecArray<T, N> {
             self.len = new_len;
             // SAFETY: array[..len] is available
             unsafe {
-                std::ptr::drop_in_place(MaybeUninit::slice_assume_init_mut(
-                    &mut self.array[new_len..len],
-                ));
+                std::ptr::drop_in_place(self.array[new_len..len].assume_init_mut());
             }
         }
     }
@@ -51,6 +82,189 @@ impl<T, const N: usize> VecArray<T, N> {
     pub fn clear(&mut self) {
         self.truncate(0);
     }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn remaining_capacity(&self) -> usize {
+        N - self.len
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: array[..len] is available
+        unsafe { self.array[..self.len].assume_init_ref() }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: array[..len] is available
+        unsafe { self.array[..self.len].assume_init_mut() }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.array.as_mut_ptr().cast()
+    }
+
+    /// 插入到index处，之后的元素后移。index > len时panic
+    pub fn insert(&mut self, index: usize, data: T) -> Result<(), CapacityError<T>> {
+        let len = self.len;
+        assert!(
+            index <= len,
+            "insertion index {index} is out of bounds (len {len})"
+        );
+        if self.is_full() {
+            return Err(CapacityError::new(data));
+        }
+        // SAFETY: 未满，array[index..len]后移一位后仍在数组内
+        unsafe {
+            let p = self.as_mut_ptr().add(index);
+            ptr::copy(p, p.add(1), len - index);
+            ptr::write(p, data);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// 移除index处的元素，之后的元素前移。index >= len时panic
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len;
+        assert!(
+            index < len,
+            "removal index {index} is out of bounds (len {len})"
+        );
+        // SAFETY: array[index]可用，移出后用后面的元素覆盖
+        unsafe {
+            let p = self.as_mut_ptr().add(index);
+            let data = ptr::read(p);
+            ptr::copy(p.add(1), p, len - index - 1);
+            self.len -= 1;
+            data
+        }
+    }
+
+    /// 用最后一个元素填补被移除的位置，O(1)但不保持顺序。index >= len时panic
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len;
+        assert!(
+            index < len,
+            "swap_remove index {index} is out of bounds (len {len})"
+        );
+        self.as_mut_slice().swap(index, len - 1);
+        self.pop().unwrap()
+    }
+
+    /// 只保留f返回true的元素，保持原有顺序
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let len = self.len;
+        // f panic时剩余元素只会泄漏，不会被重复drop
+        self.len = 0;
+        let mut kept = 0;
+        let base = self.as_mut_ptr();
+        for i in 0..len {
+            // SAFETY: array[i]可用，kept <= i
+            unsafe {
+                let p = base.add(i);
+                if f(&*p) {
+                    if kept != i {
+                        ptr::copy_nonoverlapping(p, base.add(kept), 1);
+                    }
+                    kept += 1;
+                } else {
+                    ptr::drop_in_place(p);
+                }
+            }
+        }
+        self.len = kept;
+    }
+
+    /// 移出range内的元素，Drain被drop时后面的元素前移补齐
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, N> {
+        let len = self.len;
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.checked_add(1).expect("range start overflow"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.checked_add(1).expect("range end overflow"),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => len,
+        };
+        assert!(
+            start <= end && end <= len,
+            "drain range {start}..{end} is out of bounds (len {len})"
+        );
+        // Drain被mem::forget时只保留[..start]，其余元素泄漏
+        self.len = start;
+        Drain {
+            arr: self,
+            next: start,
+            end,
+            tail_start: end,
+            tail_len: len - end,
+        }
+    }
+
+    /// 空间不足时不添加任何元素
+    pub fn extend_from_slice(&mut self, other: &[T]) -> Result<(), CapacityError>
+    where
+        T: Clone,
+    {
+        if other.len() > self.remaining_capacity() {
+            return Err(CapacityError::new(()));
+        }
+        for data in other {
+            // 已检查容量，不会失败
+            let _ = self.push(data.clone());
+        }
+        Ok(())
+    }
+
+    /// 逐个添加直到满，迭代器还有剩余元素时返回Err，带回第一个放不下的元素，已添加的保留
+    pub fn try_extend<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), CapacityError<T>> {
+        for data in iter {
+            self.push(data)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Default for VecArray<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for VecArray<T, N> {
+    fn clone(&self) -> Self {
+        let mut arr = Self::new();
+        for data in self.iter() {
+            // 容量相同，不会失败
+            let _ = arr.push(data.clone());
+        }
+        arr
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for VecArray<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for VecArray<T, N> {}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for VecArray<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
 }
 
 impl<T, const N: usize> Drop for VecArray<T, N> {
@@ -62,8 +276,148 @@ impl<T, const N: usize> Drop for VecArray<T, N> {
 impl<T, const N: usize> Deref for VecArray<T, N> {
     type Target = [T];
     fn deref(&self) -> &Self::Target {
-        // SAFETY: array[..len] is available
-        unsafe { MaybeUninit::slice_assume_init_ref(&self.array[..self.len]) }
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for VecArray<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+pub struct Drain<'a, T, const N: usize> {
+    arr: &'a mut VecArray<T, N>,
+    // 尚未移出的范围[next, end)
+    next: usize,
+    end: usize,
+    // 范围之后的元素
+    tail_start: usize,
+    tail_len: usize,
+}
+
+impl<T, const N: usize> Iterator for Drain<'_, T, N> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            self.next += 1;
+            // SAFETY: array[next-1]可用且只移出一次
+            Some(unsafe { self.arr.array[self.next - 1].assume_init_read() })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.next;
+        (n, Some(n))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Drain<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            self.end -= 1;
+            // SAFETY: array[end]可用且只移出一次
+            Some(unsafe { self.arr.array[self.end].assume_init_read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Drain<'_, T, N> {}
+
+impl<T, const N: usize> Drop for Drain<'_, T, N> {
+    fn drop(&mut self) {
+        // 先drop未取出的元素，再把tail前移到arr.len处
+        self.for_each(drop);
+        let start = self.arr.len;
+        // SAFETY: array[tail_start..tail_start+tail_len]可用，移动后只属于arr
+        unsafe {
+            let base = self.arr.as_mut_ptr();
+            ptr::copy(base.add(self.tail_start), base.add(start), self.tail_len);
+        }
+        self.arr.len = start + self.tail_len;
+    }
+}
+
+pub struct IntoIter<T, const N: usize> {
+    array: [MaybeUninit<T>; N],
+    // 尚未取出的范围[next, end)
+    next: usize,
+    end: usize,
+}
+
+impl<T, const N: usize> IntoIterator for VecArray<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+    fn into_iter(self) -> Self::IntoIter {
+        let arr = ManuallyDrop::new(self);
+        IntoIter {
+            // SAFETY: arr不会再被drop，元素的所有权转移到IntoIter
+            array: unsafe { ptr::read(&arr.array) },
+            next: 0,
+            end: arr.len,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a VecArray<T, N> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut VecArray<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            self.next += 1;
+            // SAFETY: array[next-1]可用且只移出一次
+            Some(unsafe { self.array[self.next - 1].assume_init_read() })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.next;
+        (n, Some(n))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            self.end -= 1;
+            // SAFETY: array[end]可用且只移出一次
+            Some(unsafe { self.array[self.end].assume_init_read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+    fn drop(&mut self) {
+        let (next, end) = (self.next, self.end);
+        // SAFETY: array[next..end] is available
+        unsafe {
+            std::ptr::drop_in_place(self.array[next..end].assume_init_mut());
+        }
     }
 }
 
@@ -98,6 +452,14 @@ fn test_vec_array_truncate() {
     arr[4];
 }
 
+#[test]
+#[should_panic(expected = "range end overflow")]
+fn test_vec_array_drain_overflow() {
+    let mut arr = VecArray::<u32, 5>::new();
+    arr.push(1).unwrap();
+    arr.drain(..=usize::MAX);
+}
+
 /// cargo t test_vec_array_drop -- --nocapture
 #[test]
 fn test_vec_array_drop() {
@@ -113,3 +475,66 @@ fn test_vec_array_drop() {
     arr.push(Foo).unwrap();
     arr.push(Foo).unwrap();
 }
+
+#[test]
+fn test_vec_array_api() {
+    let mut arr = VecArray::<u32, 6>::default();
+    arr.extend_from_slice(&[1, 2, 3]).unwrap();
+    arr.insert(0, 0).unwrap();
+    arr.insert(4, 4).unwrap();
+    assert_eq!(arr.as_slice(), [0, 1, 2, 3, 4]);
+    assert_eq!(arr.remove(1), 1);
+    assert_eq!(arr.swap_remove(0), 0);
+    assert_eq!(arr.as_slice(), [4, 2, 3]);
+    arr.sort();
+    arr[0] = 10;
+    assert_eq!(format!("{arr:?}"), "[10, 3, 4]");
+    assert_eq!(arr.remaining_capacity(), 3);
+
+    // 空间不足
+    assert!(arr.extend_from_slice(&[0; 4]).is_err());
+    assert_eq!(arr.len(), 3);
+    assert_eq!(arr.try_extend(5..10).unwrap_err().element(), 8);
+    assert!(arr.is_full());
+    assert_eq!(arr.capacity(), 6);
+    assert_eq!(arr.insert(0, 0), Err(CapacityError::new(0)));
+
+    let copy = arr.clone();
+    arr.retain(|x| x % 2 == 0);
+    assert_eq!(arr.as_slice(), [10, 4, 6]);
+    assert_ne!(arr, copy);
+
+    let drained: Vec<_> = arr.drain(..=1).collect();
+    assert_eq!(drained, [10, 4]);
+    assert_eq!(arr.as_slice(), [6]);
+    assert_eq!(
+        copy.into_iter().rev().collect::<Vec<_>>(),
+        [7, 6, 5, 4, 3, 10]
+    );
+}
+
+#[test]
+fn test_vec_array_api_drop() {
+    use std::rc::Rc;
+
+    let item = Rc::new(());
+    let mut arr = VecArray::<Rc<()>, 8>::new();
+    arr.try_extend(std::iter::repeat_n(item.clone(), 8))
+        .unwrap();
+    let mut i = 0;
+    arr.retain(|_| {
+        i += 1;
+        i % 2 == 0
+    });
+    assert_eq!(arr.len(), 4);
+    assert_eq!(Rc::strong_count(&item), 5);
+
+    // 未取完的Drain和IntoIter在drop时释放剩余元素
+    let len = arr.len();
+    drop(arr.drain(..2).next());
+    assert_eq!(arr.len(), len - 2);
+    let mut iter = arr.into_iter();
+    iter.next();
+    drop(iter);
+    assert_eq!(Rc::strong_count(&item), 1);
+}
@@ -1,4 +1,3 @@
-#![feature(maybe_uninit_uninit_array, maybe_uninit_slice)]
-
+pub mod cache;
 pub mod collections;
 pub mod sort;
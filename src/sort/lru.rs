@@ -1,130 +1,801 @@
+use std::borrow::Borrow;
 use std::collections::HashMap;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
 use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::collections::doubly_linked_list::{self, DoublyLinkedList, NodeHandle};
 
 /// Least Recently Used，缓存淘汰
+///
+/// 元素存放在[`DoublyLinkedList`]中，头部为最近使用，HashMap只保存节点句柄。
+/// HashMap扩容搬移的只是句柄，节点地址在被移除前保持不变。
+/// key只在节点中存一份，map的key是指向节点内key的指针。
 pub struct LRUCache<K, V> {
-    cache: HashMap<K, Node<K, V>>,
-    head: NonNull<Node<K, V>>, // 为节省空间只定义为指针
-    tail: NonNull<Node<K, V>>,
+    cache: HashMap<KeyRef<K>, NodeHandle<Entry<K, V>>>,
+    list: DoublyLinkedList<Entry<K, V>>,
     capacity: usize,
+    listener: Option<EvictionListener<K, V>>,
+    default_ttl: Option<Duration>,
+    clock: Box<dyn Clock>,
+    weigher: Option<Weigher<K, V>>,
+    // 所有元素的总重量，未设置weigher时每个元素重1
+    weight: usize,
+    max_weight: usize,
+    // None为未开启统计
+    stats: Option<CacheStats>,
+}
+
+/// 元素离开缓存的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// 超出容量被淘汰
+    Capacity,
+    /// 被`invalidate`/`clear`主动移除
+    Removed,
+    /// 同key的新值覆盖了旧值
+    Replaced,
+    /// 超过存活时间
+    Expired,
+}
+
+type EvictionListener<K, V> = Box<dyn FnMut(K, V, EvictionReason) + Send>;
+
+/// 命中统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// 新key插入次数
+    pub inserts: u64,
+    /// 已有key覆盖次数
+    pub updates: u64,
+    /// 因容量或过期被淘汰的次数，不含主动移除
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// 命中率，没有查询时为0
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
 }
 
-struct Node<K, V> {
+// 与Clock一样可能通过&self调用，要求Sync
+type Weigher<K, V> = Box<dyn Fn(&K, &V) -> usize + Send + Sync>;
+
+/// 时间来源，测试时可替换为[`ManualClock`]避免sleep。
+/// `peek`等方法通过&self读取时间，缓存共享到多个线程时会被并发调用，所以要求Sync
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// 手动拨动的时钟，clone出的副本共享同一时间
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub fn advance(&self, d: Duration) {
+        *self.now.lock().unwrap() += d;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+struct Entry<K, V> {
     key: K,
     value: V,
-    prev: NonNull<Node<K, V>>,
-    next: NonNull<Node<K, V>>,
+    // None为永不过期
+    expires_at: Option<Instant>,
+    // 插入时计算，之后通过get_mut修改value需调用reweigh更新
+    weight: usize,
+}
+
+// 指向节点内的key，节点存活期间有效
+struct KeyRef<K> {
+    k: *const K,
+}
+
+impl<K: Hash> Hash for KeyRef<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // SAFETY: KeyRef只存在于map中，对应节点此时必定存活
+        unsafe { (*self.k).hash(state) }
+    }
+}
+
+impl<K: PartialEq> PartialEq for KeyRef<K> {
+    fn eq(&self, other: &Self) -> bool {
+        // SAFETY: 同hash
+        unsafe { (*self.k).eq(&*other.k) }
+    }
+}
+
+impl<K: Eq> Eq for KeyRef<K> {}
+
+// 用Q查询map，不能直接为KeyRef<K>实现Borrow<Q>，否则与Borrow<T> for T冲突，
+// 多包一层透明的KeyWrapper<Q>
+#[repr(transparent)]
+struct KeyWrapper<Q: ?Sized>(Q);
+
+impl<Q: ?Sized> KeyWrapper<Q> {
+    fn from_ref(key: &Q) -> &Self {
+        // SAFETY: repr(transparent)，内存布局与Q相同
+        unsafe { &*(key as *const Q as *const KeyWrapper<Q>) }
+    }
+}
+
+impl<Q: ?Sized + Hash> Hash for KeyWrapper<Q> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<Q: ?Sized + PartialEq> PartialEq for KeyWrapper<Q> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<Q: ?Sized + Eq> Eq for KeyWrapper<Q> {}
+
+impl<K, Q> Borrow<KeyWrapper<Q>> for KeyRef<K>
+where
+    K: Borrow<Q>,
+    Q: ?Sized,
+{
+    fn borrow(&self) -> &KeyWrapper<Q> {
+        // SAFETY: 同hash
+        KeyWrapper::from_ref(unsafe { &*self.k }.borrow())
+    }
+}
+
+// SAFETY: 节点只归内部链表所有，句柄与key指针不会泄露到外部。
+// 其余字段中clock和weigher本身是Sync；listener是FnMut，只能通过&mut self调用，共享引用无法触及
+unsafe impl<K: Send, V: Send> Send for LRUCache<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LRUCache<K, V> {}
+
+impl<K, V> LRUCache<K, V> {
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 当前总重量
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    pub fn max_weight(&self) -> usize {
+        self.max_weight
+    }
+
+    /// 开启命中统计，已开启时不清零
+    pub fn enable_stats(&mut self) {
+        self.stats.get_or_insert_with(CacheStats::default);
+    }
+
+    /// 未开启统计时为None
+    pub fn stats(&self) -> Option<CacheStats> {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        if let Some(stats) = self.stats.as_mut() {
+            *stats = CacheStats::default();
+        }
+    }
+
+    fn record(&mut self, f: impl FnOnce(&mut CacheStats)) {
+        if let Some(stats) = self.stats.as_mut() {
+            f(stats);
+        }
+    }
+
+    /// 最久未使用且未过期的元素，不改变顺序。
+    /// 从尾部跳过已过期但尚未移除的元素，最坏O(n)
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        let entry = self.list.iter().rev().find(|e| !self.is_expired(e))?;
+        Some((&entry.key, &entry.value))
+    }
+
+    /// 新插入元素的默认存活时间，None为永不过期
+    pub fn set_default_ttl(&mut self, ttl: Option<Duration>) {
+        self.default_ttl = ttl;
+    }
+
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+    }
+
+    fn is_expired(&self, entry: &Entry<K, V>) -> bool {
+        // 未设置过期时间时不读时钟
+        entry.expires_at.is_some_and(|t| t <= self.clock.now())
+    }
+
+    fn expires_at(&self, ttl: Option<Duration>) -> Option<Instant> {
+        // 溢出视为永不过期
+        ttl.and_then(|ttl| self.clock.now().checked_add(ttl))
+    }
+
+    /// 从最近使用到最久未使用遍历，`rev()`反向
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.list.iter(),
+        }
+    }
+
+    /// 按重量限制时，修改value不会更新重量，需对修改过的key调用[`reweigh`](Self::reweigh)
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.list.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn show_keys(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.keys().cloned().collect()
+    }
+
+    // 激活node到最前
+    fn activate(&mut self, node: NodeHandle<Entry<K, V>>) {
+        // SAFETY: node都是从HashMap里查出来的，属于self.list且存活，下同
+        unsafe { self.list.move_to_front(node) }
+    }
+
+    // 指向节点内key，用作map的key。用裸指针取址，不产生新的引用
+    fn key_ref(node: NodeHandle<Entry<K, V>>) -> KeyRef<K> {
+        KeyRef {
+            // SAFETY: node指向存活的节点
+            k: unsafe { &raw const (*node.as_ptr()).key },
+        }
+    }
+
+    // 释放整条链表，不处理map
+    fn free_list(&mut self) {
+        self.list.clear();
+        self.weight = 0;
+    }
 }
 
-impl<K: Hash + Eq + Clone + std::fmt::Debug, V: Clone> LRUCache<K, V> {
+impl<K: Hash + Eq, V> LRUCache<K, V> {
     pub fn new_with_capacity(capacity: usize) -> Self {
         assert!(capacity > 0);
         Self {
             cache: HashMap::with_capacity(capacity),
-            head: NonNull::dangling(),
-            tail: NonNull::dangling(),
+            list: DoublyLinkedList::new(),
             capacity,
+            listener: None,
+            default_ttl: None,
+            clock: Box::new(SystemClock),
+            weigher: None,
+            weight: 0,
+            max_weight: usize::MAX,
+            stats: None,
         }
     }
 
-    pub fn put(&mut self, key: K, value: V) {
-        let node = if let Some(node) = self.cache.get_mut(&key) {
-            node as *mut _
-        } else {
-            // 缓存已满，删除tail
-            if self.cache.len() >= self.capacity {
-                // SAFETY: cache非空时，tail指针已被初始化
-                let key = unsafe { &self.tail.as_ref().key };
-                self.remove_link(self.tail.as_ptr());
-                self.cache.remove(key).unwrap();
+    /// 按总重量限制容量，元素重量由weigher计算，不限制元素个数
+    pub fn new_with_weigher<F>(max_weight: usize, weigher: F) -> Self
+    where
+        F: Fn(&K, &V) -> usize + Send + Sync + 'static,
+    {
+        assert!(max_weight > 0);
+        let mut cache = Self::unbounded();
+        cache.weigher = Some(Box::new(weigher));
+        cache.max_weight = max_weight;
+        cache
+    }
+
+    /// 不限容量，由调用方自行pop_lru控制大小，用于组合其他淘汰策略
+    pub fn unbounded() -> Self {
+        let mut cache = Self::new_with_capacity(1);
+        cache.cache = HashMap::new();
+        cache.capacity = usize::MAX;
+        cache
+    }
+
+    /// 设置淘汰回调，淘汰、覆盖、过期以及`invalidate`/`clear`移除的元素都会交给回调，
+    /// 可在丢弃前写回脏数据。`remove`/`pop_lru`/`drain`把元素交还调用方，不触发回调
+    pub fn on_evict<F>(&mut self, f: F)
+    where
+        F: FnMut(K, V, EvictionReason) + Send + 'static,
+    {
+        self.listener = Some(Box::new(f));
+    }
+
+    /// 插入或覆盖，缓存已满时淘汰最久未使用的元素并返回。设置了淘汰回调时元素交给回调，返回None。
+    ///
+    /// 按重量限制时：一次可能淘汰多个元素，只有第一个会被返回，需要全部接收请用`on_evict`；
+    /// 重量超过`max_weight`的元素不会插入，原样返回，同key的旧值按覆盖处理
+    pub fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.insert(key, value, self.default_ttl)
+    }
+
+    /// 插入并指定该元素的存活时间，覆盖默认值
+    pub fn put_with_ttl(&mut self, key: K, value: V, ttl: Duration) -> Option<(K, V)> {
+        self.insert(key, value, Some(ttl))
+    }
+
+    fn insert(&mut self, key: K, value: V, ttl: Option<Duration>) -> Option<(K, V)> {
+        let expires_at = self.expires_at(ttl);
+        let weight = self.weigher.as_ref().map_or(1, |f| f(&key, &value));
+        if weight > self.max_weight {
+            if let Some(node) = self.cache.remove(KeyWrapper::from_ref(&key)) {
+                let (old_key, old) = self.take_node(node);
+                self.notify(old_key, old, EvictionReason::Replaced);
+            }
+            return Some((key, value));
+        }
+
+        if let Some(node) = self.node(&key) {
+            // SAFETY: map中的句柄都指向存活的节点
+            let entry = unsafe { self.list.get_mut(node) };
+            self.weight = self.weight - entry.weight + weight;
+            entry.expires_at = expires_at;
+            entry.weight = weight;
+            let old = std::mem::replace(&mut entry.value, value);
+            self.activate(node);
+            self.record(|s| s.updates += 1);
+            self.notify(key, old, EvictionReason::Replaced);
+            // 新值更重时淘汰其他元素，node已在队首，不会淘汰自身
+            let mut evicted = None;
+            while self.weight > self.max_weight {
+                let e = self.evict_lru();
+                evicted = evicted.or(e);
             }
+            return evicted;
+        }
 
-            let node = Node {
-                key: key.clone(),
-                value,
-                prev: NonNull::dangling(),
-                next: NonNull::dangling(),
-            };
-            self.cache.insert(key.clone(), node);
-            self.cache.get_mut(&key).unwrap() as *mut _
+        // 缓存已满，删除tail
+        let mut evicted = None;
+        while !self.is_empty()
+            && (self.cache.len() >= self.capacity || self.weight + weight > self.max_weight)
+        {
+            let e = self.evict_lru();
+            evicted = evicted.or(e);
+        }
+
+        self.weight += weight;
+        let node = self.list.push_front_node(Entry {
+            key,
+            value,
+            expires_at,
+            weight,
+        });
+        self.cache.insert(Self::key_ref(node), node);
+        self.record(|s| s.inserts += 1);
+        evicted
+    }
+
+    // 按key查节点，K: Borrow<Q>时可以用&Q查询，例如String用&str
+    fn node<Q>(&self, key: &Q) -> Option<NodeHandle<Entry<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.cache.get(KeyWrapper::from_ref(key)).copied()
+    }
+
+    pub fn get<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        V: Clone,
+    {
+        self.get_ref(key).cloned()
+    }
+
+    /// 查询并激活，返回引用避免clone
+    pub fn get_ref<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// 查询并激活，已过期的元素在此时移除。
+    /// 按重量限制时，修改value不会更新重量，需之后调用[`reweigh`](Self::reweigh)
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let Some(node) = self.node(key) else {
+            self.record(|s| s.misses += 1);
+            return None;
         };
+        // SAFETY: map中的句柄都指向存活的节点，下同
+        if self.is_expired(unsafe { self.list.get(node) }) {
+            self.cache.remove(&Self::key_ref(node));
+            let (k, v) = self.take_node(node);
+            self.notify(k, v, EvictionReason::Expired);
+            self.record(|s| s.misses += 1);
+            return None;
+        }
+        self.record(|s| s.hits += 1);
+        self.activate(node);
+        unsafe { Some(&mut self.list.get_mut(node).value) }
+    }
 
-        self.push_front_link(node);
+    /// 命中时同get_mut，否则用f计算并插入。
+    /// 按重量限制时计算结果超过`max_weight`不会插入，丢弃后返回None；
+    /// 通过返回的引用修改value后同样需要调用[`reweigh`](Self::reweigh)
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> Option<&mut V>
+    where
+        F: FnOnce() -> V,
+    {
+        if let Some(v) = self.get_mut(&key) {
+            let v = NonNull::from(v);
+            // SAFETY: 指向存活节点的值，只是绕开借用检查对条件返回的限制
+            return Some(unsafe { &mut *v.as_ptr() });
+        }
+        let value = f();
+        if self.weigher.as_ref().map_or(1, |w| w(&key, &value)) > self.max_weight {
+            return None;
+        }
+        self.put(key, value);
+        // 新节点在队首
+        self.list.front_mut().map(|e| &mut e.value)
     }
 
-    pub fn get(&mut self, key: &K) -> Option<V> {
-        if let Some(node) = self.cache.get_mut(key) {
-            let node = node as *mut _;
-            self.activate(node);
-            unsafe { Some((*node).value.clone()) }
-        } else {
-            None
+    /// 重新计算key的重量并激活，通过`get_mut`/`iter_mut`修改了value后调用。
+    /// 超过`max_weight`时移除该元素，否则淘汰其他元素直到总重量不超限，返回值同`put`
+    pub fn reweigh<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let node = self.node(key)?;
+        // 未设置weigher时每个元素重1，不会变化
+        let weigher = self.weigher.as_ref()?;
+        // SAFETY: map中的句柄都指向存活的节点
+        let entry = unsafe { self.list.get_mut(node) };
+        let weight = weigher(&entry.key, &entry.value);
+        self.weight = self.weight - entry.weight + weight;
+        entry.weight = weight;
+        if weight > self.max_weight {
+            self.cache.remove(&Self::key_ref(node));
+            let (k, v) = self.take_node(node);
+            return self.notify(k, v, EvictionReason::Capacity);
         }
+        // 先激活，淘汰时不会淘汰自身
+        self.activate(node);
+        let mut evicted = None;
+        while self.weight > self.max_weight {
+            let e = self.evict_lru();
+            evicted = evicted.or(e);
+        }
+        evicted
     }
 
-    // 激活node到最前
-    fn activate(&mut self, node_ptr: *mut Node<K, V>) {
-        let node = NonNull::new(node_ptr).unwrap();
-        if self.head == node {
-            return;
+    /// 查询但不改变使用顺序，过期元素视为不存在但不移除
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        // SAFETY: map中的句柄都指向存活的节点
+        let entry = unsafe { self.list.get(self.node(key)?) };
+        (!self.is_expired(entry)).then_some(&entry.value)
+    }
+
+    /// 同peek，返回可变引用。按重量限制时修改后需调用[`reweigh`](Self::reweigh)
+    pub fn peek_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let node = self.node(key)?;
+        // SAFETY: map中的句柄都指向存活的节点
+        if self.is_expired(unsafe { self.list.get(node) }) {
+            return None;
         }
-        self.remove_link(node_ptr);
-        self.push_front_link(node_ptr);
+        Some(unsafe { &mut self.list.get_mut(node).value })
     }
 
-    // 在链表中删除。修改前节点next，后节点prev，若首节点修改head，若尾节点修改tail
-    fn remove_link(&mut self, node_ptr: *mut Node<K, V>) {
-        let node = NonNull::new(node_ptr).unwrap();
-        // SAFTY: node都是从HashMap里查出来的，必不为空
-        unsafe {
-            // 后节点prev
-            if (*node_ptr).next != NonNull::dangling() {
-                (*node_ptr).next.as_mut().prev = (*node_ptr).prev;
-            }
-            // 前节点next
-            if (*node_ptr).prev != NonNull::dangling() {
-                (*node_ptr).prev.as_mut().next = (*node_ptr).next;
-            }
-            // 如果该node是tail，修改tail
-            if self.tail == node {
-                self.tail = (*node_ptr).prev;
-            }
-        };
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.peek(key).is_some()
     }
 
-    // 插入到单链表头。修改自身prev/next，head，后节点prev，若尾节点修改tail
-    fn push_front_link(&mut self, node_ptr: *mut Node<K, V>) {
-        let node = NonNull::new(node_ptr).unwrap();
-        // SAFTY: node都是从HashMap里查出来的
-        unsafe {
-            // 自身next，由于已在队首prev dangling即可
-            (*node_ptr).next = self.head;
-            (*node_ptr).prev = NonNull::dangling();
-            // 插入第一个元素时初始化tail
-            if self.tail == NonNull::dangling() {
-                self.tail = node;
-            } else {
-                // 后节点prev
-                self.head.as_mut().prev = node;
+    /// 移除所有过期元素并交给淘汰回调，返回移除个数
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let mut count = 0;
+        let mut p = self.list.back_node();
+        while let Some(node) = p {
+            // SAFETY: 链表中的节点都存活，摘下当前节点前先记下prev
+            let (prev, expired) = unsafe {
+                let entry = self.list.get(node);
+                (
+                    self.list.prev_node(node),
+                    entry.expires_at.is_some_and(|t| t <= now),
+                )
+            };
+            if expired {
+                self.cache.remove(&Self::key_ref(node));
+                let (k, v) = self.take_node(node);
+                self.notify(k, v, EvictionReason::Expired);
+                count += 1;
             }
-            self.head = node;
-        };
+            p = prev;
+        }
+        count
     }
 
-    pub fn show_keys(&self) -> Vec<K>
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let node = self.cache.remove(KeyWrapper::from_ref(key))?;
+        Some(self.take_node(node).1)
+    }
+
+    /// 移除元素并交给淘汰回调，返回元素是否存在
+    pub fn invalidate<Q>(&mut self, key: &Q) -> bool
     where
-        K: std::fmt::Debug,
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
     {
-        let mut v = vec![];
-        let mut p = self.head;
-        while p != NonNull::dangling() {
-            unsafe {
-                v.push(p.as_ref().key.clone());
-                p = p.as_ref().next;
+        match self.cache.remove(KeyWrapper::from_ref(key)) {
+            Some(node) => {
+                let (k, v) = self.take_node(node);
+                self.notify(k, v, EvictionReason::Removed);
+                true
             }
+            None => false,
         }
-        v
+    }
+
+    /// 移除并返回最久未使用的元素
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let tail = self.list.back_node()?;
+        self.cache.remove(&Self::key_ref(tail));
+        Some(self.take_node(tail))
+    }
+
+    pub fn clear(&mut self) {
+        if self.listener.is_none() {
+            self.cache.clear();
+            self.free_list();
+            return;
+        }
+        while let Some((k, v)) = self.pop_lru() {
+            self.notify(k, v, EvictionReason::Removed);
+        }
+    }
+
+    /// 修改容量，缩容时从尾部淘汰多余元素
+    pub fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0);
+        self.capacity = new_capacity;
+        while self.cache.len() > self.capacity {
+            self.evict_lru();
+        }
+    }
+
+    // 因容量淘汰尾部元素
+    fn evict_lru(&mut self) -> Option<(K, V)> {
+        self.pop_lru()
+            .and_then(|(k, v)| self.notify(k, v, EvictionReason::Capacity))
+    }
+
+    // 有回调时交给回调，否则原样返回
+    fn notify(&mut self, key: K, value: V, reason: EvictionReason) -> Option<(K, V)> {
+        if matches!(reason, EvictionReason::Capacity | EvictionReason::Expired) {
+            self.record(|s| s.evictions += 1);
+        }
+        match self.listener.as_mut() {
+            Some(f) => {
+                f(key, value, reason);
+                None
+            }
+            None => Some((key, value)),
+        }
+    }
+
+    // 移除并返回最近使用的元素
+    fn pop_mru(&mut self) -> Option<(K, V)> {
+        let head = self.list.front_node()?;
+        self.cache.remove(&Self::key_ref(head));
+        Some(self.take_node(head))
+    }
+
+    // 从链表摘下并释放节点，调用方负责从map中删除
+    fn take_node(&mut self, node: NodeHandle<Entry<K, V>>) -> (K, V) {
+        // SAFETY: node属于self.list且存活，之后不再使用
+        let entry = unsafe { self.list.remove_node(node) };
+        self.weight -= entry.weight;
+        (entry.key, entry.value)
+    }
+
+    /// 按使用顺序移出全部元素，迭代器drop时清空剩余元素
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        Drain { cache: self }
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    inner: doubly_linked_list::Iter<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|e| (&e.key, &e.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|e| (&e.key, &e.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    inner: doubly_linked_list::IterMut<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|e| (&e.key, &mut e.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|e| (&e.key, &mut e.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+pub struct IntoIter<K: Hash + Eq, V> {
+    cache: LRUCache<K, V>,
+}
+
+impl<K: Hash + Eq, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cache.pop_mru()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.cache.len(), Some(self.cache.len()))
+    }
+}
+
+impl<K: Hash + Eq, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.cache.pop_lru()
+    }
+}
+
+impl<K: Hash + Eq, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K: Hash + Eq, V> FusedIterator for IntoIter<K, V> {}
+
+pub struct Drain<'a, K: Hash + Eq, V> {
+    cache: &'a mut LRUCache<K, V>,
+}
+
+impl<K: Hash + Eq, V> Iterator for Drain<'_, K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cache.pop_mru()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.cache.len(), Some(self.cache.len()))
+    }
+}
+
+impl<K: Hash + Eq, V> DoubleEndedIterator for Drain<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.cache.pop_lru()
+    }
+}
+
+impl<K: Hash + Eq, V> ExactSizeIterator for Drain<'_, K, V> {}
+impl<K: Hash + Eq, V> FusedIterator for Drain<'_, K, V> {}
+
+impl<K: Hash + Eq, V> Drop for Drain<'_, K, V> {
+    fn drop(&mut self) {
+        // 剩余元素同样视为交还调用方，直接丢弃不触发回调
+        self.cache.cache.clear();
+        self.cache.free_list();
+    }
+}
+
+impl<K: Hash + Eq, V> IntoIterator for LRUCache<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { cache: self }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a LRUCache<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut LRUCache<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
@@ -146,3 +817,316 @@ fn test_lru() {
     assert_eq!(cache.get(&5).unwrap(), 5);
     assert_eq!(cache.get(&6).unwrap(), 6);
 }
+
+/// HashMap多次扩容后链表依然有效，cargo +nightly miri test test_lru_rehash
+#[test]
+fn test_lru_rehash() {
+    use std::collections::VecDeque;
+
+    // 对照组，队首为最近使用
+    let mut expect: VecDeque<(u32, String)> = VecDeque::new();
+    let mut cache = LRUCache::new_with_capacity(50);
+    // 预分配后再把map缩到最小，之后的插入会反复触发扩容
+    cache.cache.shrink_to_fit();
+    for i in 0..200u32 {
+        // 覆盖已有key
+        let value = if i % 7 == 0 { i * 10 } else { i }.to_string();
+        cache.put(i % 120, value.clone());
+        expect.retain(|(k, _)| *k != i % 120);
+        expect.push_front((i % 120, value));
+        expect.truncate(50);
+        if i % 3 == 0 {
+            let k = i / 2;
+            let hit = cache.get(&k);
+            let pos = expect.iter().position(|(x, _)| *x == k);
+            assert_eq!(hit, pos.map(|pos| expect[pos].1.clone()));
+            if let Some(pos) = pos {
+                let entry = expect.remove(pos).unwrap();
+                expect.push_front(entry);
+            }
+        }
+    }
+    let keys = expect.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+    assert_eq!(cache.show_keys(), keys);
+}
+
+#[test]
+fn test_lru_api() {
+    let mut cache = LRUCache::new_with_capacity(3);
+    assert!(cache.is_empty());
+    assert!(cache.put(1, "a").is_none());
+    assert!(cache.put(2, "b").is_none());
+    assert!(cache.put(3, "c").is_none());
+    assert_eq!(cache.put(4, "d"), Some((1, "a")));
+    assert_eq!(cache.len(), 3);
+
+    // peek不激活，2依旧最久未使用
+    assert_eq!(cache.peek(&2), Some(&"b"));
+    assert_eq!(cache.peek_lru(), Some((&2, &"b")));
+    *cache.get_mut(&2).unwrap() = "B";
+    assert_eq!(cache.peek_lru(), Some((&3, &"c")));
+    assert_eq!(cache.get_ref(&2), Some(&"B"));
+
+    assert!(cache.contains_key(&3));
+    assert_eq!(cache.remove(&3), Some("c"));
+    assert!(!cache.contains_key(&3));
+    assert_eq!(cache.remove(&3), None);
+    assert_eq!(cache.show_keys(), [2, 4]);
+
+    assert_eq!(cache.pop_lru(), Some((4, "d")));
+    cache.put(5, "e");
+    cache.put(6, "f");
+    cache.resize(1);
+    assert_eq!(cache.show_keys(), [6]);
+    assert_eq!(cache.capacity(), 1);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert!(cache.peek_lru().is_none());
+    assert!(cache.pop_lru().is_none());
+}
+
+#[test]
+fn test_lru_iter() {
+    let mut cache = LRUCache::new_with_capacity(4);
+    for i in 0..4 {
+        cache.put(i, i * 10);
+    }
+    cache.get(&1);
+    let keys = cache.keys().copied().collect::<Vec<_>>();
+    assert_eq!(keys, [1, 3, 2, 0]);
+    let values = cache.values().rev().copied().collect::<Vec<_>>();
+    assert_eq!(values, [0, 20, 30, 10]);
+
+    // 首尾交替，相遇后停止
+    let mut iter = cache.iter();
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next(), Some((&1, &10)));
+    assert_eq!(iter.next_back(), Some((&0, &0)));
+    assert_eq!(iter.next(), Some((&3, &30)));
+    assert_eq!(iter.next_back(), Some((&2, &20)));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    for (k, v) in cache.iter_mut() {
+        *v += k;
+    }
+    // 遍历不改变使用顺序
+    assert_eq!(cache.peek_lru(), Some((&0, &0)));
+
+    let drained = cache.drain().take(1).collect::<Vec<_>>();
+    assert_eq!(drained, [(1, 11)]);
+    assert!(cache.is_empty());
+
+    for i in 0..4 {
+        cache.put(i, i);
+    }
+    let owned = cache.into_iter().rev().collect::<Vec<_>>();
+    assert_eq!(owned, [(0, 0), (1, 1), (2, 2), (3, 3)]);
+}
+
+#[test]
+fn test_lru_on_evict() {
+    let evicted = Arc::new(Mutex::new(vec![]));
+    let mut cache = LRUCache::new_with_capacity(2);
+    let log = evicted.clone();
+    cache.on_evict(move |k, v, reason| log.lock().unwrap().push((k, v, reason)));
+
+    cache.put(1, "a");
+    cache.put(2, "b");
+    // 淘汰交给回调，不再返回
+    assert!(cache.put(3, "c").is_none());
+    cache.put(3, "C");
+    assert!(cache.invalidate(&2));
+    assert!(!cache.invalidate(&2));
+    // remove交还调用方，不触发回调
+    assert_eq!(cache.remove(&3), Some("C"));
+    cache.put(4, "d");
+    cache.put(5, "e");
+    cache.clear();
+
+    use EvictionReason::*;
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        [
+            (1, "a", Capacity),
+            (3, "c", Replaced),
+            (2, "b", Removed),
+            (4, "d", Removed),
+            (5, "e", Removed),
+        ]
+    );
+}
+
+#[test]
+fn test_lru_ttl() {
+    let clock = ManualClock::new();
+    let mut cache = LRUCache::new_with_capacity(4);
+    cache.set_clock(clock.clone());
+    cache.set_default_ttl(Some(Duration::from_secs(10)));
+    let expired = Arc::new(Mutex::new(vec![]));
+    let log = expired.clone();
+    cache.on_evict(move |k, _, reason| {
+        if reason == EvictionReason::Expired {
+            log.lock().unwrap().push(k);
+        }
+    });
+
+    cache.put(1, 1);
+    cache.put_with_ttl(2, 2, Duration::from_secs(5));
+    cache.put_with_ttl(3, 3, Duration::from_secs(20));
+    cache.set_default_ttl(None);
+    cache.put(4, 4);
+
+    clock.advance(Duration::from_secs(5));
+    // peek不移除过期元素
+    assert!(cache.peek(&2).is_none());
+    assert!(!cache.contains_key(&2));
+    assert_eq!(cache.len(), 4);
+    // get时惰性移除
+    assert!(cache.get(&2).is_none());
+    assert_eq!(cache.len(), 3);
+
+    clock.advance(Duration::from_secs(5));
+    // 覆盖后按新的ttl计算
+    cache.put_with_ttl(3, 30, Duration::from_secs(1));
+    clock.advance(Duration::from_secs(1));
+    // 尾部的1已过期，peek_lru跳过它
+    assert_eq!(cache.peek_lru(), Some((&4, &4)));
+    assert_eq!(cache.purge_expired(), 2);
+    assert_eq!(cache.show_keys(), [4]);
+    assert_eq!(*expired.lock().unwrap(), [2, 1, 3]);
+
+    clock.advance(Duration::from_secs(1000));
+    assert_eq!(cache.get(&4), Some(4));
+}
+
+#[test]
+fn test_lru_weigher() {
+    let mut cache = LRUCache::new_with_weigher(10, |_: &u32, v: &String| v.len());
+    cache.put(1, "aaaa".to_owned());
+    cache.put(2, "bbb".to_owned());
+    cache.put(3, "cc".to_owned());
+    assert_eq!(cache.weight(), 9);
+
+    // 需要淘汰两个元素才放得下
+    let evicted = Arc::new(Mutex::new(vec![]));
+    let log = evicted.clone();
+    cache.on_evict(move |k, _, reason| log.lock().unwrap().push((k, reason)));
+    cache.put(4, "dddddd".to_owned());
+    assert_eq!(cache.show_keys(), [4, 3]);
+    assert_eq!(cache.weight(), 8);
+
+    // 超过总重量的元素被拒绝，旧值同时失效
+    let big = "x".repeat(11);
+    assert_eq!(cache.put(3, big.clone()), Some((3, big)));
+    assert_eq!(cache.show_keys(), [4]);
+    assert_eq!(cache.weight(), 6);
+
+    // 覆盖为更重的值时淘汰其他元素
+    cache.put(5, "e".to_owned());
+    cache.put(4, "d".repeat(10));
+    assert_eq!(cache.show_keys(), [4]);
+    assert_eq!(cache.weight(), 10);
+
+    use EvictionReason::*;
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        [
+            (1, Capacity),
+            (2, Capacity),
+            (3, Replaced),
+            (4, Replaced),
+            (5, Capacity),
+        ]
+    );
+    cache.clear();
+    assert_eq!(cache.weight(), 0);
+
+    // 通过get_mut修改后需重新计算重量
+    cache.put(1, "a".to_owned());
+    cache.put(2, "b".to_owned());
+    cache.get_mut(&1).unwrap().push_str("aaaa");
+    cache.get_mut(&2).unwrap().push_str("bbbbb");
+    assert_eq!(cache.weight(), 2);
+    cache.reweigh(&1);
+    assert_eq!(cache.weight(), 6);
+    cache.reweigh(&2);
+    assert_eq!(cache.show_keys(), [2]);
+    assert_eq!(cache.weight(), 6);
+}
+
+#[test]
+fn test_lru_stats() {
+    let mut cache = LRUCache::new_with_capacity(2);
+    cache.put(1, 1);
+    assert!(cache.stats().is_none());
+
+    cache.enable_stats();
+    cache.put(2, 2);
+    cache.put(2, 20);
+    cache.put(3, 3);
+    cache.get(&2);
+    cache.get(&3);
+    cache.get(&1);
+    // peek不计入统计
+    cache.peek(&2);
+    let stats = cache.stats().unwrap();
+    assert_eq!(
+        stats,
+        CacheStats {
+            hits: 2,
+            misses: 1,
+            inserts: 2,
+            updates: 1,
+            evictions: 1,
+        }
+    );
+    assert!((stats.hit_ratio() - 2.0 / 3.0).abs() < f64::EPSILON);
+
+    cache.reset_stats();
+    assert_eq!(cache.stats(), Some(CacheStats::default()));
+    assert_eq!(CacheStats::default().hit_ratio(), 0.0);
+}
+
+#[test]
+fn test_lru_borrow() {
+    let mut cache = LRUCache::new_with_capacity(2);
+    cache.put("a".to_owned(), 1);
+    cache.put("b".to_owned(), 2);
+    // 用&str查询String key
+    assert_eq!(cache.get("a"), Some(1));
+    assert_eq!(cache.peek("b"), Some(&2));
+    assert!(cache.contains_key("a"));
+    *cache.get_mut("a").unwrap() += 10;
+    assert_eq!(cache.remove("a"), Some(11));
+    assert!(!cache.contains_key("a"));
+    assert!(cache.invalidate("b"));
+    assert!(cache.is_empty());
+
+    // key不需要Clone
+    #[derive(Hash, PartialEq, Eq)]
+    struct Key(u32);
+    let mut cache = LRUCache::new_with_capacity(1);
+    cache.put(Key(1), 1);
+    assert_eq!(cache.put(Key(2), 2).map(|(k, v)| (k.0, v)), Some((1, 1)));
+    assert_eq!(cache.get(&Key(2)), Some(2));
+}
+
+#[test]
+fn test_lru_get_or_insert() {
+    let mut cache = LRUCache::new_with_capacity(2);
+    cache.put(1, 1);
+    cache.put(2, 2);
+    // 命中时不调用f
+    assert_eq!(cache.get_or_insert_with(1, || unreachable!()), Some(&mut 1));
+    *cache.get_or_insert_with(3, || 3).unwrap() += 10;
+    assert_eq!(cache.show_keys(), [3, 1]);
+    assert_eq!(cache.peek(&3), Some(&13));
+
+    let mut cache = LRUCache::new_with_weigher(5, |_: &u32, v: &String| v.len());
+    assert!(cache
+        .get_or_insert_with(1, || "too long".to_owned())
+        .is_none());
+    assert!(cache.is_empty());
+}
@@ -1,78 +1,933 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::{self, Future};
 use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
 
 use tokio::sync::{mpsc, oneshot};
 
-use crate::sort::lru::LRUCache;
+use crate::sort::lru::{CacheStats, LRUCache};
 
 /// Least Recently Used，缓存淘汰，无锁封装
 #[derive(Clone)]
 pub struct LRUCacheHandle<K, V> {
     // cache: Arc<LRUCache<K, V>>,
-    tx: mpsc::UnboundedSender<(Command<K, V>, oneshot::Sender<Option<V>>)>,
+    tx: Tx<K, V>,
+    shared: Arc<Shared>,
 }
 
-enum Command<K, V> {
+// 与处理线程共享的状态
+#[derive(Default)]
+struct Shared {
+    // 处理线程是否因panic退出
+    panicked: AtomicBool,
+    // 无界通道（含有界模式的控制通道）中已发送未处理的消息数，有界通道的占用由通道自身给出
+    queued: AtomicUsize,
+    // queue_len的峰值
+    peak: AtomicUsize,
+}
+
+#[derive(Clone)]
+enum Tx<K, V> {
+    Unbounded(mpsc::UnboundedSender<Message<K, V>>),
+    // 队列满时发送方等待。另带一个无界的控制通道，供Drop中无法等待的消息使用
+    Bounded(
+        mpsc::Sender<Message<K, V>>,
+        mpsc::UnboundedSender<Message<K, V>>,
+    ),
+}
+
+enum Rx<K, V> {
+    Unbounded(mpsc::UnboundedReceiver<Message<K, V>>),
+    Bounded(
+        mpsc::Receiver<Message<K, V>>,
+        mpsc::UnboundedReceiver<Message<K, V>>,
+    ),
+}
+
+impl<K, V> Rx<K, V> {
+    // 取自无界通道的消息从queued中减去
+    async fn recv(&mut self, queued: &AtomicUsize) -> Option<Message<K, V>> {
+        match self {
+            Rx::Unbounded(rx) => {
+                let msg = rx.recv().await?;
+                queued.fetch_sub(1, Ordering::Relaxed);
+                Some(msg)
+            }
+            // 控制通道优先。两个通道的发送端都在handle中，同时关闭，以主通道为准
+            Rx::Bounded(rx, ctl) => {
+                future::poll_fn(|cx| match ctl.poll_recv(cx) {
+                    Poll::Ready(Some(msg)) => {
+                        queued.fetch_sub(1, Ordering::Relaxed);
+                        Poll::Ready(Some(msg))
+                    }
+                    _ => rx.poll_recv(cx),
+                })
+                .await
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        match self {
+            Rx::Unbounded(rx) => rx.close(),
+            Rx::Bounded(rx, ctl) => {
+                rx.close();
+                ctl.close();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheError {
+    /// 处理线程已正常退出，例如已shutdown
+    Closed,
+    /// 处理线程panic
+    Panicked,
+    /// 有界队列已满，只有`try_put`会返回，其他调用等待队列腾出位置
+    Full,
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Closed => write!(f, "cache handler closed"),
+            CacheError::Panicked => write!(f, "cache handler panicked"),
+            CacheError::Full => write!(f, "cache queue full"),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+/// 批量执行的单条命令，Get/Remove回复对应的值，Put回复None
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command<K, V> {
     Put(K, V),
     Get(K),
+    Remove(K),
+}
+
+/// [`LRUCacheHandle`]与[`ShardedLru`](crate::sort::lru_sharded::ShardedLru)共同的同步接口，
+/// 调用方对其泛型即可在两种实现间切换
+pub trait ConcurrentCache<K, V>: Clone + Send + Sync {
+    fn get(&self, key: K) -> Result<Option<V>, CacheError>;
+
+    fn put(&self, key: K, value: V) -> Result<(), CacheError>;
+
+    fn remove(&self, key: K) -> Result<Option<V>, CacheError>;
+
+    /// 依次执行，是否与其他调用方的命令互斥由实现决定
+    fn batch(&self, cmds: Vec<Command<K, V>>) -> Result<Vec<Option<V>>, CacheError>;
+
+    fn get_many(&self, keys: Vec<K>) -> Result<Vec<Option<V>>, CacheError> {
+        self.batch(keys.into_iter().map(Command::Get).collect())
+    }
+
+    fn put_many(&self, entries: Vec<(K, V)>) -> Result<(), CacheError> {
+        let cmds = entries.into_iter().map(|(k, v)| Command::Put(k, v));
+        self.batch(cmds.collect()).map(|_| ())
+    }
+
+    /// 不等待处理结果的put
+    fn put_nowait(&self, key: K, value: V) -> Result<(), CacheError>;
+
+    fn enable_stats(&self) -> Result<(), CacheError>;
+
+    fn stats(&self) -> Result<Option<CacheStats>, CacheError>;
+
+    fn reset_stats(&self) -> Result<(), CacheError>;
+
+    /// 取出全部内容，之后其他clone出的实例是否可用由实现决定
+    fn shutdown(self) -> Result<Vec<(K, V)>, CacheError>;
+}
+
+enum Message<K, V> {
+    Command(Command<K, V>, oneshot::Sender<Option<V>>),
+    Batch(Vec<Command<K, V>>, oneshot::Sender<Vec<Option<V>>>),
+    // 不需要回复的put
+    Put(K, V),
+    Stats(oneshot::Sender<Option<CacheStats>>),
+    EnableStats,
+    ResetStats,
+    Shutdown(oneshot::Sender<Vec<(K, V)>>),
+    Load(K, oneshot::Sender<Load<V>>),
+    // 加载完成写入缓存并通知等待者，None表示加载方放弃
+    Loaded(K, Option<V>),
+}
+
+enum Load<V> {
+    Hit(V),
+    // 由调用方执行loader
+    Leader,
+    // 同key已有调用方在加载
+    Wait(oneshot::Receiver<Option<V>>),
+}
+
+// 正在加载的key
+type Pending<K, V> = HashMap<K, Loading<V>>;
+
+struct Loading<V> {
+    waiters: Vec<oneshot::Sender<Option<V>>>,
+    // 加载期间该key被put或remove过，加载结果已过时，不再写入缓存
+    dirty: bool,
+}
+
+// 加载方的loader未完成就被drop（取消或panic）时通知等待者重新竞争
+struct LoadGuard<'a, K: Hash + Eq + Clone + fmt::Debug + Send + 'static, V: Clone + Send + 'static>
+{
+    handle: &'a LRUCacheHandle<K, V>,
+    key: Option<K>,
+}
+
+impl<K: Hash + Eq + Clone + fmt::Debug + Send + 'static, V: Clone + Send + 'static> Drop
+    for LoadGuard<'_, K, V>
+{
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.handle.send_detached(Message::Loaded(key, None));
+        }
+    }
+}
+
+// drop时若正在panic则记下，须先于回复通道drop，调用方才能看到Panicked
+struct PanicGuard<'a>(&'a AtomicBool);
+
+impl Drop for PanicGuard<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.store(true, Ordering::Release);
+        }
+    }
 }
 
 impl<K: Hash + Eq + Clone + std::fmt::Debug + Send + 'static, V: Clone + Send + 'static>
     LRUCacheHandle<K, V>
 {
+    /// 在单独的线程中运行缓存
     pub fn new(capacity: usize) -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
-        std::thread::spawn(move || Self::handler(capacity, rx));
-        Self { tx }
+        Self::new_joinable(capacity).0
+    }
+
+    /// 同new，返回处理线程的JoinHandle，可用于等待其退出
+    pub fn new_joinable(capacity: usize) -> (Self, std::thread::JoinHandle<()>) {
+        Self::start_thread(capacity, None)
+    }
+
+    /// 消息队列最多容纳queue_capacity条，满时调用方等待处理线程跟上
+    pub fn new_bounded(capacity: usize, queue_capacity: usize) -> Self {
+        Self::start_thread(capacity, Some(queue_capacity)).0
+    }
+
+    /// 在当前tokio runtime中以task运行缓存，必须在runtime内调用
+    pub fn spawn(capacity: usize) -> Self {
+        Self::spawn_joinable(capacity).0
+    }
+
+    pub fn spawn_joinable(capacity: usize) -> (Self, tokio::task::JoinHandle<()>) {
+        Self::start_task(capacity, None)
+    }
+
+    pub fn spawn_bounded(capacity: usize, queue_capacity: usize) -> Self {
+        Self::start_task(capacity, Some(queue_capacity)).0
+    }
+
+    fn start_thread(
+        capacity: usize,
+        queue_capacity: Option<usize>,
+    ) -> (Self, std::thread::JoinHandle<()>) {
+        let (handle, rx) = Self::channel(queue_capacity);
+        let shared = handle.shared.clone();
+        // 线程内用单线程runtime驱动，与task版共用处理逻辑
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("failed to build cache runtime");
+        let join = std::thread::spawn(move || rt.block_on(Self::handler(capacity, rx, shared)));
+        (handle, join)
+    }
+
+    fn start_task(
+        capacity: usize,
+        queue_capacity: Option<usize>,
+    ) -> (Self, tokio::task::JoinHandle<()>) {
+        let (handle, rx) = Self::channel(queue_capacity);
+        let join = tokio::spawn(Self::handler(capacity, rx, handle.shared.clone()));
+        (handle, join)
+    }
+
+    fn channel(queue_capacity: Option<usize>) -> (Self, Rx<K, V>) {
+        let (tx, rx) = match queue_capacity {
+            Some(n) => {
+                let (tx, rx) = mpsc::channel(n);
+                let (ctl_tx, ctl_rx) = mpsc::unbounded_channel();
+                (Tx::Bounded(tx, ctl_tx), Rx::Bounded(rx, ctl_rx))
+            }
+            None => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                (Tx::Unbounded(tx), Rx::Unbounded(rx))
+            }
+        };
+        let shared = Arc::new(Shared::default());
+        (Self { tx, shared }, rx)
+    }
+
+    /// 阻塞等待处理结果，不能在tokio runtime内调用，异步代码请用`*_async`版本
+    pub fn get(&self, key: K) -> Result<Option<V>, CacheError> {
+        self.blocking_call(Command::Get(key))
+    }
+
+    pub fn put(&self, key: K, value: V) -> Result<(), CacheError> {
+        self.blocking_call(Command::Put(key, value)).map(|_| ())
+    }
+
+    pub fn remove(&self, key: K) -> Result<Option<V>, CacheError> {
+        self.blocking_call(Command::Remove(key))
+    }
+
+    /// 异步版本，等待处理结果时不阻塞线程
+    pub async fn get_async(&self, key: K) -> Result<Option<V>, CacheError> {
+        self.call(Command::Get(key)).await
+    }
+
+    pub async fn put_async(&self, key: K, value: V) -> Result<(), CacheError> {
+        self.call(Command::Put(key, value)).await.map(|_| ())
+    }
+
+    pub async fn remove_async(&self, key: K) -> Result<Option<V>, CacheError> {
+        self.call(Command::Remove(key)).await
+    }
+
+    /// 一条消息内依次执行，期间不会穿插其他调用方的命令
+    pub async fn batch_async(
+        &self,
+        cmds: Vec<Command<K, V>>,
+    ) -> Result<Vec<Option<V>>, CacheError> {
+        self.ask(|otx| Message::Batch(cmds, otx)).await
+    }
+
+    pub fn batch(&self, cmds: Vec<Command<K, V>>) -> Result<Vec<Option<V>>, CacheError> {
+        self.blocking_ask(|otx| Message::Batch(cmds, otx))
+    }
+
+    pub async fn get_many_async(&self, keys: Vec<K>) -> Result<Vec<Option<V>>, CacheError> {
+        self.batch_async(keys.into_iter().map(Command::Get).collect())
+            .await
+    }
+
+    pub fn get_many(&self, keys: Vec<K>) -> Result<Vec<Option<V>>, CacheError> {
+        self.batch(keys.into_iter().map(Command::Get).collect())
+    }
+
+    pub async fn put_many_async(&self, entries: Vec<(K, V)>) -> Result<(), CacheError> {
+        let cmds = entries.into_iter().map(|(k, v)| Command::Put(k, v));
+        self.batch_async(cmds.collect()).await.map(|_| ())
+    }
+
+    pub fn put_many(&self, entries: Vec<(K, V)>) -> Result<(), CacheError> {
+        let cmds = entries.into_iter().map(|(k, v)| Command::Put(k, v));
+        self.batch(cmds.collect()).map(|_| ())
+    }
+
+    /// 不等待处理结果，有界队列满时等待腾出位置，不能在tokio runtime内调用
+    pub fn put_nowait(&self, key: K, value: V) -> Result<(), CacheError> {
+        self.blocking_send(Message::Put(key, value))
+    }
+
+    pub async fn put_nowait_async(&self, key: K, value: V) -> Result<(), CacheError> {
+        self.send_async(Message::Put(key, value)).await
+    }
+
+    /// 同put_nowait，但有界队列满时不等待，直接返回`CacheError::Full`
+    pub fn try_put(&self, key: K, value: V) -> Result<(), CacheError> {
+        self.send(Message::Put(key, value))
+    }
+
+    /// 已发送但处理线程还未取走的消息数，不含正在等待队列腾出位置的调用
+    pub fn queue_len(&self) -> usize {
+        let queued = self.shared.queued.load(Ordering::Relaxed);
+        match &self.tx {
+            Tx::Unbounded(_) => queued,
+            Tx::Bounded(tx, _) => queued + tx.max_capacity() - tx.capacity(),
+        }
+    }
+
+    /// queue_len的历史峰值
+    pub fn queue_peak(&self) -> usize {
+        self.shared.peak.load(Ordering::Relaxed)
+    }
+
+    /// 无界队列为None
+    pub fn queue_capacity(&self) -> Option<usize> {
+        match &self.tx {
+            Tx::Unbounded(_) => None,
+            Tx::Bounded(tx, _) => Some(tx.max_capacity()),
+        }
+    }
+
+    /// 未命中时执行loader并写入缓存。同一key并发未命中时只有一个调用方执行loader，
+    /// 其他调用方等待其结果；执行中的调用方被取消时，由等待者之一重新加载
+    pub async fn get_or_load<F, Fut>(&self, key: K, loader: F) -> Result<V, CacheError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let mut loader = Some(loader);
+        loop {
+            match self.ask(|otx| Message::Load(key.clone(), otx)).await? {
+                Load::Hit(v) => return Ok(v),
+                Load::Wait(wrx) => match wrx.await {
+                    Ok(Some(v)) => return Ok(v),
+                    // 加载方放弃，重新竞争
+                    Ok(None) => continue,
+                    Err(_) => return Err(self.error()),
+                },
+                Load::Leader => {
+                    let mut guard = LoadGuard {
+                        handle: self,
+                        key: Some(key),
+                    };
+                    // 每个调用方最多成为一次加载方
+                    let v = loader.take().unwrap()().await;
+                    // 队列满时异步等待，等待中被取消则仍由guard通知等待者
+                    let key = guard.key.clone().unwrap();
+                    let _ = self.send_async(Message::Loaded(key, Some(v.clone()))).await;
+                    guard.key = None;
+                    return Ok(v);
+                }
+            }
+        }
+    }
+
+    /// 开启命中统计，不等待处理结果，有界队列满时等待腾出位置
+    pub fn enable_stats(&self) -> Result<(), CacheError> {
+        self.blocking_send(Message::EnableStats)
+    }
+
+    pub async fn enable_stats_async(&self) -> Result<(), CacheError> {
+        self.send_async(Message::EnableStats).await
+    }
+
+    /// 未开启统计时为None
+    pub async fn stats_async(&self) -> Result<Option<CacheStats>, CacheError> {
+        self.ask(Message::Stats).await
+    }
+
+    pub fn stats(&self) -> Result<Option<CacheStats>, CacheError> {
+        self.blocking_ask(Message::Stats)
+    }
+
+    pub fn reset_stats(&self) -> Result<(), CacheError> {
+        self.blocking_send(Message::ResetStats)
+    }
+
+    pub async fn reset_stats_async(&self) -> Result<(), CacheError> {
+        self.send_async(Message::ResetStats).await
+    }
+
+    /// 停止处理线程：不再接收新命令，已排队的命令处理完后，按最近使用到最久未使用返回缓存内容。
+    /// 其他clone出的handle之后的调用都返回`CacheError::Closed`
+    pub async fn shutdown_async(self) -> Result<Vec<(K, V)>, CacheError> {
+        self.ask(Message::Shutdown).await
     }
 
-    pub fn get(&self, key: K) -> Option<V> {
+    pub fn shutdown(self) -> Result<Vec<(K, V)>, CacheError> {
+        self.blocking_ask(Message::Shutdown)
+    }
+
+    // 通道断开时区分处理线程是正常退出还是panic
+    fn error(&self) -> CacheError {
+        if self.shared.panicked.load(Ordering::Acquire) {
+            CacheError::Panicked
+        } else {
+            CacheError::Closed
+        }
+    }
+
+    // 不等待的发送，有界队列满时返回Full
+    fn send(&self, msg: Message<K, V>) -> Result<(), CacheError> {
+        match &self.tx {
+            Tx::Unbounded(tx) => self.send_unbounded(tx, msg),
+            Tx::Bounded(tx, _) => {
+                tx.try_send(msg).map_err(|e| match e {
+                    mpsc::error::TrySendError::Full(_) => CacheError::Full,
+                    mpsc::error::TrySendError::Closed(_) => self.error(),
+                })?;
+                self.update_peak();
+                Ok(())
+            }
+        }
+    }
+
+    async fn send_async(&self, msg: Message<K, V>) -> Result<(), CacheError> {
+        match &self.tx {
+            Tx::Unbounded(tx) => self.send_unbounded(tx, msg),
+            Tx::Bounded(tx, _) => {
+                tx.send(msg).await.map_err(|_| self.error())?;
+                self.update_peak();
+                Ok(())
+            }
+        }
+    }
+
+    fn blocking_send(&self, msg: Message<K, V>) -> Result<(), CacheError> {
+        match &self.tx {
+            Tx::Unbounded(tx) => self.send_unbounded(tx, msg),
+            Tx::Bounded(tx, _) => {
+                tx.blocking_send(msg).map_err(|_| self.error())?;
+                self.update_peak();
+                Ok(())
+            }
+        }
+    }
+
+    // 必须送达且无法等待的消息，只用于Drop中。有界模式下走不受容量限制的控制通道
+    fn send_detached(&self, msg: Message<K, V>) {
+        let (Tx::Unbounded(tx) | Tx::Bounded(_, tx)) = &self.tx;
+        let _ = self.send_unbounded(tx, msg);
+    }
+
+    // 发送前先计数，避免处理线程先减后加时下溢
+    fn send_unbounded(
+        &self,
+        tx: &mpsc::UnboundedSender<Message<K, V>>,
+        msg: Message<K, V>,
+    ) -> Result<(), CacheError> {
+        self.shared.queued.fetch_add(1, Ordering::Relaxed);
+        if tx.send(msg).is_err() {
+            self.shared.queued.fetch_sub(1, Ordering::Relaxed);
+            return Err(self.error());
+        }
+        self.update_peak();
+        Ok(())
+    }
+
+    fn update_peak(&self) {
+        self.shared
+            .peak
+            .fetch_max(self.queue_len(), Ordering::Relaxed);
+    }
+
+    async fn call(&self, cmd: Command<K, V>) -> Result<Option<V>, CacheError> {
+        self.ask(|otx| Message::Command(cmd, otx)).await
+    }
+
+    fn blocking_call(&self, cmd: Command<K, V>) -> Result<Option<V>, CacheError> {
+        self.blocking_ask(|otx| Message::Command(cmd, otx))
+    }
+
+    // 发送带回复通道的消息并等待回复
+    async fn ask<T>(
+        &self,
+        msg: impl FnOnce(oneshot::Sender<T>) -> Message<K, V>,
+    ) -> Result<T, CacheError> {
         let (otx, orx) = oneshot::channel();
-        let _ = self.tx.send((Command::Get(key), otx));
-        orx.blocking_recv().unwrap()
+        self.send_async(msg(otx)).await?;
+        orx.await.map_err(|_| self.error())
     }
 
-    pub fn put(&self, key: K, value: V) {
+    fn blocking_ask<T>(
+        &self,
+        msg: impl FnOnce(oneshot::Sender<T>) -> Message<K, V>,
+    ) -> Result<T, CacheError> {
         let (otx, orx) = oneshot::channel();
-        let _ = self.tx.send((Command::Put(key, value), otx));
-        orx.blocking_recv().unwrap();
+        self.blocking_send(msg(otx))?;
+        orx.blocking_recv().map_err(|_| self.error())
     }
 
-    fn handler(
-        capacity: usize,
-        mut rx: mpsc::UnboundedReceiver<(Command<K, V>, oneshot::Sender<Option<V>>)>,
-    ) {
+    async fn handler(capacity: usize, mut rx: Rx<K, V>, shared: Arc<Shared>) {
+        let _guard = PanicGuard(&shared.panicked);
         let mut cache = LRUCache::<K, V>::new_with_capacity(capacity);
-        while let Some((cmd, otx)) = rx.blocking_recv() {
-            match cmd {
-                Command::Put(k, v) => {
-                    cache.put(k, v);
-                    let _ = otx.send(None);
+        let mut pending = Pending::new();
+        while let Some(msg) = rx.recv(&shared.queued).await {
+            if let Some(otx) = Self::process(&mut cache, &mut pending, msg, &shared.panicked) {
+                rx.close();
+                while let Some(msg) = rx.recv(&shared.queued).await {
+                    Self::process(&mut cache, &mut pending, msg, &shared.panicked);
+                }
+                let _ = otx.send(cache.drain().collect());
+                break;
+            }
+        }
+    }
+
+    // 收到Shutdown时返回其回复通道，由调用方收尾。收尾期间再次收到的Shutdown直接丢弃
+    fn process(
+        cache: &mut LRUCache<K, V>,
+        pending: &mut Pending<K, V>,
+        msg: Message<K, V>,
+        panicked: &AtomicBool,
+    ) -> Option<oneshot::Sender<Vec<(K, V)>>> {
+        match msg {
+            Message::Command(cmd, otx) => {
+                reply(otx, panicked, || Self::execute(cache, pending, cmd))
+            }
+            Message::Batch(cmds, otx) => reply(otx, panicked, || {
+                cmds.into_iter()
+                    .map(|cmd| Self::execute(cache, pending, cmd))
+                    .collect()
+            }),
+            Message::Put(k, v) => {
+                Self::execute(cache, pending, Command::Put(k, v));
+            }
+            Message::Stats(otx) => reply(otx, panicked, || cache.stats()),
+            Message::EnableStats => cache.enable_stats(),
+            Message::ResetStats => cache.reset_stats(),
+            Message::Shutdown(otx) => return Some(otx),
+            Message::Load(k, otx) => reply(otx, panicked, || {
+                if let Some(v) = cache.get(&k) {
+                    return Load::Hit(v);
+                }
+                if let Some(loading) = pending.get_mut(&k) {
+                    let (wtx, wrx) = oneshot::channel();
+                    loading.waiters.push(wtx);
+                    return Load::Wait(wrx);
                 }
-                Command::Get(k) => {
-                    let _ = otx.send(cache.get(&k));
+                pending.insert(
+                    k,
+                    Loading {
+                        waiters: Vec::new(),
+                        dirty: false,
+                    },
+                );
+                Load::Leader
+            }),
+            Message::Loaded(k, v) => {
+                if let Some(loading) = pending.remove(&k) {
+                    for waiter in loading.waiters {
+                        let _ = waiter.send(v.clone());
+                    }
+                    if let (Some(v), false) = (v, loading.dirty) {
+                        cache.put(k, v);
+                    }
                 }
             }
         }
+        None
+    }
+
+    fn execute(
+        cache: &mut LRUCache<K, V>,
+        pending: &mut Pending<K, V>,
+        cmd: Command<K, V>,
+    ) -> Option<V> {
+        match cmd {
+            Command::Put(k, v) => {
+                Self::invalidate(pending, &k);
+                cache.put(k, v);
+                None
+            }
+            Command::Get(k) => cache.get(&k),
+            Command::Remove(k) => {
+                Self::invalidate(pending, &k);
+                cache.remove(&k)
+            }
+        }
+    }
+
+    // 正在加载的key被直接修改时，以修改为准
+    fn invalidate(pending: &mut Pending<K, V>, key: &K) {
+        if let Some(loading) = pending.get_mut(key) {
+            loading.dirty = true;
+        }
     }
 }
 
+impl<K, V> ConcurrentCache<K, V> for LRUCacheHandle<K, V>
+where
+    K: Hash + Eq + Clone + fmt::Debug + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    fn get(&self, key: K) -> Result<Option<V>, CacheError> {
+        LRUCacheHandle::get(self, key)
+    }
+
+    fn put(&self, key: K, value: V) -> Result<(), CacheError> {
+        LRUCacheHandle::put(self, key, value)
+    }
+
+    fn remove(&self, key: K) -> Result<Option<V>, CacheError> {
+        LRUCacheHandle::remove(self, key)
+    }
+
+    fn batch(&self, cmds: Vec<Command<K, V>>) -> Result<Vec<Option<V>>, CacheError> {
+        LRUCacheHandle::batch(self, cmds)
+    }
+
+    fn put_nowait(&self, key: K, value: V) -> Result<(), CacheError> {
+        LRUCacheHandle::put_nowait(self, key, value)
+    }
+
+    fn enable_stats(&self) -> Result<(), CacheError> {
+        LRUCacheHandle::enable_stats(self)
+    }
+
+    fn stats(&self) -> Result<Option<CacheStats>, CacheError> {
+        LRUCacheHandle::stats(self)
+    }
+
+    fn reset_stats(&self) -> Result<(), CacheError> {
+        LRUCacheHandle::reset_stats(self)
+    }
+
+    fn shutdown(self) -> Result<Vec<(K, V)>, CacheError> {
+        LRUCacheHandle::shutdown(self)
+    }
+}
+
+// 执行f并回复。参数otx在局部变量guard之后drop，f中panic时先记下再断开回复通道
+fn reply<T>(otx: oneshot::Sender<T>, panicked: &AtomicBool, f: impl FnOnce() -> T) {
+    let _guard = PanicGuard(panicked);
+    // 先求值，otx.send(f())会在f执行前移出otx
+    let value = f();
+    let _ = otx.send(value);
+}
+
 #[test]
 fn test_lru_lockfree() {
     let handle = LRUCacheHandle::new(5);
     let handle2 = handle.clone();
     let h = std::thread::spawn(move || {
         for i in 0..5 {
-            handle2.put(i, i);
+            handle2.put(i, i).unwrap();
         }
-        handle2.get(1).unwrap();
+        handle2.get(1).unwrap().unwrap();
     });
     h.join().unwrap();
 
-    handle.put(5, 5);
-    handle.put(6, 6);
+    handle.put(5, 5).unwrap();
+    handle.put(6, 6).unwrap();
+
+    assert!(handle.get(0).unwrap().is_none());
+    assert!(handle.get(2).unwrap().is_none());
+    assert_eq!(handle.get(5).unwrap().unwrap(), 5);
+    assert_eq!(handle.get(6).unwrap().unwrap(), 6);
+}
+
+#[test]
+fn test_lru_lockfree_stats() {
+    let handle = LRUCacheHandle::new(2);
+    assert!(handle.stats().unwrap().is_none());
+    handle.enable_stats().unwrap();
+    handle.put(1, 1).unwrap();
+    handle.put(2, 2).unwrap();
+    handle.put(3, 3).unwrap();
+    handle.get(1).unwrap();
+    handle.get(3).unwrap();
+    let stats = handle.stats().unwrap().unwrap();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+    assert_eq!((stats.inserts, stats.evictions), (3, 1));
+    handle.reset_stats().unwrap();
+    assert_eq!(handle.stats().unwrap(), Some(CacheStats::default()));
+}
+
+#[test]
+fn test_lru_lockfree_async() {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        // task运行在runtime内，也可以换成new创建的线程版
+        let handle = LRUCacheHandle::spawn(2);
+        let handle2 = handle.clone();
+        tokio::spawn(async move {
+            handle2.put_async(1, 1).await.unwrap();
+            handle2.put_async(2, 2).await.unwrap();
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(handle.get_async(1).await.unwrap(), Some(1));
+        handle.put_async(3, 3).await.unwrap();
+        assert!(handle.get_async(2).await.unwrap().is_none());
+        assert_eq!(handle.remove_async(1).await.unwrap(), Some(1));
+        assert!(handle.get_async(1).await.unwrap().is_none());
+
+        let handle = LRUCacheHandle::new(2);
+        handle.put_async(1, 1).await.unwrap();
+        assert_eq!(handle.get_async(1).await.unwrap(), Some(1));
+    });
+}
+
+#[test]
+fn test_lru_lockfree_batch() {
+    let handle = LRUCacheHandle::new(3);
+    handle.put_many(vec![(1, 1), (2, 2)]).unwrap();
+    handle.put_nowait(3, 3).unwrap();
+    assert_eq!(
+        handle.get_many(vec![1, 3, 4]).unwrap(),
+        [Some(1), Some(3), None]
+    );
+    // 2最久未使用，被4挤掉
+    let replies = handle
+        .batch(vec![
+            Command::Put(4, 4),
+            Command::Get(2),
+            Command::Remove(1),
+            Command::Get(4),
+        ])
+        .unwrap();
+    assert_eq!(replies, [None, None, Some(1), Some(4)]);
+}
+
+#[test]
+fn test_lru_lockfree_bounded() {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        // current_thread runtime下task在await前不会运行，队列只进不出
+        let handle = LRUCacheHandle::spawn_bounded(10, 2);
+        assert_eq!(handle.queue_capacity(), Some(2));
+        handle.try_put(1, 1).unwrap();
+        handle.put_nowait_async(2, 2).await.unwrap();
+        assert_eq!(handle.try_put(3, 3), Err(CacheError::Full));
+        assert_eq!(handle.queue_len(), 2);
+
+        // 其他调用等待队列腾出位置
+        handle.enable_stats_async().await.unwrap();
+        handle.put_async(3, 3).await.unwrap();
+        assert_eq!(
+            handle.get_many_async(vec![1, 2, 3]).await.unwrap(),
+            [Some(1), Some(2), Some(3)]
+        );
+        assert_eq!(handle.stats_async().await.unwrap().unwrap().hits, 3);
+        assert_eq!(handle.get_or_load(4, || async { 4 }).await, Ok(4));
+        assert_eq!(handle.get_async(4).await.unwrap(), Some(4));
+        assert_eq!(handle.queue_len(), 0);
+        assert!(handle.queue_peak() <= 2);
+
+        // 队列满时加载方被取消，放弃通知经控制通道送达，等待者接手
+        let handle = LRUCacheHandle::spawn_bounded(10, 1);
+        let (started_tx, started_rx) = oneshot::channel();
+        let handle2 = handle.clone();
+        let leader = tokio::spawn(async move {
+            handle2
+                .get_or_load(5, || async {
+                    started_tx.send(()).unwrap();
+                    std::future::pending::<u32>().await
+                })
+                .await
+        });
+        started_rx.await.unwrap();
+        let handle2 = handle.clone();
+        let waiter = tokio::spawn(async move { handle2.get_or_load(5, || async { 500 }).await });
+        tokio::task::yield_now().await;
+        handle.try_put(1, 1).unwrap();
+        assert_eq!(handle.try_put(2, 2), Err(CacheError::Full));
+        leader.abort();
+        assert_eq!(waiter.await.unwrap(), Ok(500));
+    });
+    assert_eq!(LRUCacheHandle::<u32, u32>::new(1).queue_capacity(), None);
+}
+
+#[test]
+fn test_lru_lockfree_get_or_load() {
+    use std::sync::atomic::AtomicUsize;
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let handle = LRUCacheHandle::spawn(10);
+        let loads = Arc::new(AtomicUsize::new(0));
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let handle = handle.clone();
+                let loads = loads.clone();
+                tokio::spawn(async move {
+                    handle
+                        .get_or_load(1, || async {
+                            loads.fetch_add(1, Ordering::Relaxed);
+                            tokio::task::yield_now().await;
+                            100
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), Ok(100));
+        }
+        // 并发未命中只加载一次
+        assert_eq!(loads.load(Ordering::Relaxed), 1);
+        assert_eq!(handle.get_async(1).await.unwrap(), Some(100));
+
+        // 加载方被取消，等待者接手
+        let (started_tx, started_rx) = oneshot::channel();
+        let handle2 = handle.clone();
+        let leader = tokio::spawn(async move {
+            handle2
+                .get_or_load(2, || async {
+                    started_tx.send(()).unwrap();
+                    std::future::pending::<u32>().await
+                })
+                .await
+        });
+        started_rx.await.unwrap();
+        let handle2 = handle.clone();
+        let waiter = tokio::spawn(async move { handle2.get_or_load(2, || async { 200 }).await });
+        tokio::task::yield_now().await;
+        leader.abort();
+        assert_eq!(waiter.await.unwrap(), Ok(200));
+        assert_eq!(handle.get_async(2).await.unwrap(), Some(200));
+
+        // 加载期间put或remove同一key，加载结果不覆盖
+        for (key, direct) in [(3, Some(30)), (4, None)] {
+            let (started_tx, started_rx) = oneshot::channel();
+            let (done_tx, done_rx) = oneshot::channel::<u32>();
+            let handle2 = handle.clone();
+            let leader = tokio::spawn(async move {
+                handle2
+                    .get_or_load(key, || async {
+                        started_tx.send(()).unwrap();
+                        done_rx.await.unwrap()
+                    })
+                    .await
+            });
+            started_rx.await.unwrap();
+            match direct {
+                Some(v) => handle.put_async(key, v).await.unwrap(),
+                None => assert_eq!(handle.remove_async(key).await.unwrap(), None),
+            }
+            done_tx.send(key).unwrap();
+            assert_eq!(leader.await.unwrap(), Ok(key));
+            assert_eq!(handle.get_async(key).await.unwrap(), direct);
+        }
+    });
+}
+
+#[test]
+fn test_lru_lockfree_shutdown() {
+    let (handle, join) = LRUCacheHandle::new_joinable(3);
+    let handle2 = handle.clone();
+    for i in 0..4 {
+        handle.put(i, i).unwrap();
+    }
+    handle.get(1).unwrap();
+    assert_eq!(handle.shutdown().unwrap(), [(1, 1), (3, 3), (2, 2)]);
+    join.join().unwrap();
+    assert_eq!(handle2.get(1), Err(CacheError::Closed));
+    assert_eq!(handle2.enable_stats(), Err(CacheError::Closed));
+}
+
+#[test]
+fn test_lru_lockfree_panicked() {
+    // hash时panic，模拟处理线程出错
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Bomb(u32);
+    impl Hash for Bomb {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            assert_ne!(self.0, 0, "boom");
+            self.0.hash(state);
+        }
+    }
 
-    assert!(handle.get(0).is_none());
-    assert!(handle.get(2).is_none());
-    assert_eq!(handle.get(5).unwrap(), 5);
-    assert_eq!(handle.get(6).unwrap(), 6);
+    let (handle, join) = LRUCacheHandle::new_joinable(3);
+    handle.put(Bomb(1), 1).unwrap();
+    assert_eq!(handle.get(Bomb(0)), Err(CacheError::Panicked));
+    assert!(join.join().is_err());
+    assert_eq!(handle.put(Bomb(1), 1), Err(CacheError::Panicked));
 }
@@ -0,0 +1,253 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::sort::lru::{CacheStats, LRUCache};
+use crate::sort::lru_lockfree::{CacheError, Command, ConcurrentCache};
+
+/// Least Recently Used，分片加锁版
+///
+/// key按hash分到N个各自加锁的LRUCache，不同分片的操作互不阻塞。
+/// 淘汰只在分片内进行，整体近似LRU。容量均分到各分片，余数分给前几个分片，
+/// 分片数不超过容量，总容量与请求的一致
+pub struct ShardedLru<K, V> {
+    shards: Arc<[Mutex<LRUCache<K, V>>]>,
+    hasher: RandomState,
+}
+
+impl<K, V> Clone for ShardedLru<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            shards: self.shards.clone(),
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> ShardedLru<K, V> {
+    /// 分片数为CPU核数的4倍
+    pub fn new(capacity: usize) -> Self {
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::new_with_shards(capacity, cpus * 4)
+    }
+
+    /// shards超过capacity时按capacity截断，避免出现容量为0的分片
+    pub fn new_with_shards(capacity: usize, shards: usize) -> Self {
+        assert!(shards > 0);
+        let n = shards.min(capacity).max(1);
+        Self {
+            shards: (0..n)
+                .map(|i| capacity / n + usize::from(i < capacity % n))
+                .map(|cap| Mutex::new(LRUCache::new_with_capacity(cap)))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> MutexGuard<'_, LRUCache<K, V>> {
+        let idx = self.hasher.hash_one(key) as usize % self.shards.len();
+        self.shards[idx].lock().unwrap()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).get(key)
+    }
+
+    pub fn put(&self, key: K, value: V) {
+        self.shard(&key).put(key, value);
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).remove(key)
+    }
+
+    /// 逐个分片加锁，不同于`LRUCacheHandle::batch`，整体不是原子的
+    pub fn get_many(&self, keys: &[K]) -> Vec<Option<V>> {
+        keys.iter().map(|k| self.get(k)).collect()
+    }
+
+    pub fn put_many(&self, entries: impl IntoIterator<Item = (K, V)>) {
+        entries.into_iter().for_each(|(k, v)| self.put(k, v));
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| s.lock().unwrap().capacity())
+            .sum()
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn clear(&self) {
+        self.shards.iter().for_each(|s| s.lock().unwrap().clear());
+    }
+
+    pub fn enable_stats(&self) {
+        self.shards
+            .iter()
+            .for_each(|s| s.lock().unwrap().enable_stats());
+    }
+
+    /// 各分片统计之和，未开启时为None
+    pub fn stats(&self) -> Option<CacheStats> {
+        let mut total = CacheStats::default();
+        for shard in self.shards.iter() {
+            let stats = shard.lock().unwrap().stats()?;
+            total.hits += stats.hits;
+            total.misses += stats.misses;
+            total.inserts += stats.inserts;
+            total.updates += stats.updates;
+            total.evictions += stats.evictions;
+        }
+        Some(total)
+    }
+
+    pub fn reset_stats(&self) {
+        self.shards
+            .iter()
+            .for_each(|s| s.lock().unwrap().reset_stats());
+    }
+}
+
+/// 与`LRUCacheHandle`互换使用，不会返回错误。
+/// `batch`逐条加锁执行，整体不是原子的；`shutdown`按分片依次取出，不保证全局的使用顺序
+impl<K, V> ConcurrentCache<K, V> for ShardedLru<K, V>
+where
+    K: Hash + Eq + Send,
+    V: Clone + Send,
+{
+    fn get(&self, key: K) -> Result<Option<V>, CacheError> {
+        Ok(ShardedLru::get(self, &key))
+    }
+
+    fn put(&self, key: K, value: V) -> Result<(), CacheError> {
+        ShardedLru::put(self, key, value);
+        Ok(())
+    }
+
+    fn remove(&self, key: K) -> Result<Option<V>, CacheError> {
+        Ok(ShardedLru::remove(self, &key))
+    }
+
+    fn batch(&self, cmds: Vec<Command<K, V>>) -> Result<Vec<Option<V>>, CacheError> {
+        let replies = cmds.into_iter().map(|cmd| match cmd {
+            Command::Put(k, v) => {
+                ShardedLru::put(self, k, v);
+                None
+            }
+            Command::Get(k) => ShardedLru::get(self, &k),
+            Command::Remove(k) => ShardedLru::remove(self, &k),
+        });
+        Ok(replies.collect())
+    }
+
+    fn put_nowait(&self, key: K, value: V) -> Result<(), CacheError> {
+        ConcurrentCache::put(self, key, value)
+    }
+
+    fn enable_stats(&self) -> Result<(), CacheError> {
+        ShardedLru::enable_stats(self);
+        Ok(())
+    }
+
+    fn stats(&self) -> Result<Option<CacheStats>, CacheError> {
+        Ok(ShardedLru::stats(self))
+    }
+
+    fn reset_stats(&self) -> Result<(), CacheError> {
+        ShardedLru::reset_stats(self);
+        Ok(())
+    }
+
+    fn shutdown(self) -> Result<Vec<(K, V)>, CacheError> {
+        let entries = self
+            .shards
+            .iter()
+            .flat_map(|s| s.lock().unwrap().drain().collect::<Vec<_>>());
+        Ok(entries.collect())
+    }
+}
+
+#[test]
+fn test_lru_sharded() {
+    let cache = ShardedLru::new_with_shards(400, 4);
+    assert_eq!(cache.capacity(), 400);
+    cache.enable_stats();
+
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                for i in 0..25 {
+                    cache.put(t * 25 + i, i);
+                }
+            })
+        })
+        .collect();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+
+    assert_eq!(cache.len(), 100);
+    assert_eq!(cache.get(&26), Some(1));
+    assert_eq!(cache.remove(&26), Some(1));
+    assert_eq!(cache.get_many(&[0, 26, 99]), [Some(0), None, Some(24)]);
+    let stats = cache.stats().unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.inserts), (3, 1, 100));
+
+    // 每个分片只淘汰自己的元素，总数不超过容量
+    cache.put_many((1000..2000).map(|i| (i, i)));
+    assert!(cache.len() <= 400);
+    cache.clear();
+    assert!(cache.is_empty());
+
+    // 容量不能整除或小于分片数时总容量不变
+    let cache = ShardedLru::<u32, u32>::new_with_shards(10, 64);
+    assert_eq!((cache.capacity(), cache.shards()), (10, 10));
+    let cache = ShardedLru::<u32, u32>::new_with_shards(10, 4);
+    assert_eq!(cache.capacity(), 10);
+    cache.put_many((0..100).map(|i| (i, i)));
+    assert_eq!(cache.len(), 10);
+}
+
+#[test]
+fn test_lru_sharded_as_concurrent_cache() {
+    use crate::sort::lru_lockfree::LRUCacheHandle;
+
+    // 同一段代码分别跑在两种实现上
+    fn exercise<C: ConcurrentCache<u32, u32>>(cache: C) {
+        cache.enable_stats().unwrap();
+        cache.put_many(vec![(1, 1), (2, 2)]).unwrap();
+        cache.put_nowait(3, 3).unwrap();
+        assert_eq!(cache.get(1), Ok(Some(1)));
+        let replies = cache
+            .batch(vec![Command::Remove(2), Command::Get(2), Command::Get(3)])
+            .unwrap();
+        assert_eq!(replies, [Some(2), None, Some(3)]);
+        assert_eq!(cache.stats().unwrap().unwrap().hits, 2);
+        let mut entries = cache.shutdown().unwrap();
+        entries.sort();
+        assert_eq!(entries, [(1, 1), (3, 3)]);
+    }
+
+    exercise(ShardedLru::new_with_shards(100, 4));
+    exercise(LRUCacheHandle::new(100));
+}
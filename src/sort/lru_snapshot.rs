@@ -0,0 +1,318 @@
+//! LRUCache的快照与恢复，按最久未使用到最近使用的顺序写出，恢复时依次插入，使用顺序完全一致。
+//!
+//! 内置二进制格式：4字节魔数，u64元素个数，之后依次是key、value，整数都按小端序定长编码。
+//! 已过期的元素不写出，过期时间与重量函数不保存，恢复后按新缓存的设置处理
+use std::hash::Hash;
+use std::io::{self, BufWriter, Read, Write};
+
+use crate::sort::lru::LRUCache;
+
+const MAGIC: &[u8; 4] = b"LRU1";
+
+/// 内置格式的编码
+pub trait Encode {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()>;
+
+    /// 编码连续的多个元素，不含长度。默认逐个编码，u8整块写出
+    fn encode_slice<W: Write>(items: &[Self], w: &mut W) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        items.iter().try_for_each(|item| item.encode(w))
+    }
+}
+
+pub trait Decode: Sized {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self>;
+
+    /// 解码len个元素，默认逐个解码，u8整块读入
+    fn decode_vec<R: Read>(len: usize, r: &mut R) -> io::Result<Vec<Self>> {
+        // 长度来自外部数据，不据此预分配
+        (0..len).map(|_| Self::decode(r)).collect()
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+macro_rules! impl_num {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                    w.write_all(&self.to_le_bytes())
+                }
+            }
+
+            impl Decode for $t {
+                fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+                    let mut buf = [0; std::mem::size_of::<$t>()];
+                    r.read_exact(&mut buf)?;
+                    Ok(<$t>::from_le_bytes(buf))
+                }
+            }
+        )*
+    };
+}
+
+impl_num!(u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+// 字符串和字节数组整块读写，避免无缓冲时每个字节一次系统调用
+impl Encode for u8 {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&[*self])
+    }
+
+    fn encode_slice<W: Write>(items: &[Self], w: &mut W) -> io::Result<()> {
+        w.write_all(items)
+    }
+}
+
+impl Decode for u8 {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut buf = [0];
+        r.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn decode_vec<R: Read>(len: usize, r: &mut R) -> io::Result<Vec<Self>> {
+        // take限制读取长度，按实际读到的数据增长，不按len预分配
+        let mut buf = Vec::new();
+        r.take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(buf)
+    }
+}
+
+// usize/isize统一按64位编码，与平台无关
+impl Encode for usize {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u64).encode(w)
+    }
+}
+
+impl Decode for usize {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        usize::try_from(u64::decode(r)?).map_err(|_| invalid("usize overflow"))
+    }
+}
+
+impl Encode for isize {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as i64).encode(w)
+    }
+}
+
+impl Decode for isize {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        isize::try_from(i64::decode(r)?).map_err(|_| invalid("isize overflow"))
+    }
+}
+
+impl Encode for bool {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u8).encode(w)
+    }
+}
+
+impl Decode for bool {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        match u8::decode(r)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid("invalid bool")),
+        }
+    }
+}
+
+// 变长数据先写长度
+impl Encode for str {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        u8::encode_slice(self.as_bytes(), w)
+    }
+}
+
+impl Encode for String {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.as_str().encode(w)
+    }
+}
+
+impl Decode for String {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let bytes = Vec::<u8>::decode(r)?;
+        String::from_utf8(bytes).map_err(|_| invalid("invalid utf-8"))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        T::encode_slice(self, w)
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let len = usize::decode(r)?;
+        T::decode_vec(len, r)
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.0.encode(w)?;
+        self.1.encode(w)
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        Ok((A::decode(r)?, B::decode(r)?))
+    }
+}
+
+impl<K: Hash + Eq, V> LRUCache<K, V> {
+    // 未过期的元素，从最久未使用到最近使用
+    fn live_entries(&self) -> Vec<(&K, &V)> {
+        self.iter()
+            .rev()
+            .filter(|(k, _)| self.peek(*k).is_some())
+            .collect()
+    }
+
+    /// 用内置格式写出全部未过期元素，不改变使用顺序。内部带缓冲，writer不需要再包BufWriter
+    pub fn snapshot<W: Write>(&self, writer: W) -> io::Result<()>
+    where
+        K: Encode,
+        V: Encode,
+    {
+        let mut writer = BufWriter::new(writer);
+        let entries = self.live_entries();
+        writer.write_all(MAGIC)?;
+        entries.len().encode(&mut writer)?;
+        for (k, v) in entries {
+            k.encode(&mut writer)?;
+            v.encode(&mut writer)?;
+        }
+        writer.flush()
+    }
+
+    /// 从snapshot的输出恢复。capacity小于元素个数时只保留最近使用的部分。
+    /// 为了不多读快照之后的数据，内部不加缓冲，读文件时应传入`BufReader`
+    pub fn restore<R: Read>(mut reader: R, capacity: usize) -> io::Result<Self>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not an LRUCache snapshot"));
+        }
+        let len = usize::decode(&mut reader)?;
+        let mut cache = Self::new_with_capacity(capacity);
+        for _ in 0..len {
+            let k = K::decode(&mut reader)?;
+            let v = V::decode(&mut reader)?;
+            cache.put(k, v);
+        }
+        Ok(cache)
+    }
+
+    /// 同snapshot，用bincode序列化实现了serde的类型
+    #[cfg(feature = "serde")]
+    pub fn snapshot_serde<W: Write>(&self, writer: W) -> io::Result<()>
+    where
+        K: serde::Serialize,
+        V: serde::Serialize,
+    {
+        let mut writer = BufWriter::new(writer);
+        let entries = self.live_entries();
+        bincode::serialize_into(&mut writer, &(entries.len() as u64)).map_err(|e| into_io(*e))?;
+        for entry in entries {
+            bincode::serialize_into(&mut writer, &entry).map_err(|e| into_io(*e))?;
+        }
+        writer.flush()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn restore_serde<R: Read>(mut reader: R, capacity: usize) -> io::Result<Self>
+    where
+        K: serde::de::DeserializeOwned,
+        V: serde::de::DeserializeOwned,
+    {
+        let len: u64 = bincode::deserialize_from(&mut reader).map_err(|e| into_io(*e))?;
+        let mut cache = Self::new_with_capacity(capacity);
+        for _ in 0..len {
+            let (k, v) = bincode::deserialize_from(&mut reader).map_err(|e| into_io(*e))?;
+            cache.put(k, v);
+        }
+        Ok(cache)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn into_io(e: bincode::ErrorKind) -> io::Error {
+    match e {
+        bincode::ErrorKind::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+#[test]
+fn test_lru_snapshot() {
+    let mut cache = LRUCache::new_with_capacity(4);
+    for i in 0..4u32 {
+        cache.put(i.to_string(), vec![i; i as usize]);
+    }
+    cache.get("1");
+    let mut buf = Vec::new();
+    cache.snapshot(&mut buf).unwrap();
+
+    let mut restored = LRUCache::<String, Vec<u32>>::restore(&buf[..], 4).unwrap();
+    assert_eq!(restored.show_keys(), cache.show_keys());
+    assert_eq!(restored.get("3"), Some(vec![3, 3, 3]));
+
+    // 容量变小时保留最近使用的
+    let restored = LRUCache::<String, Vec<u32>>::restore(&buf[..], 2).unwrap();
+    assert_eq!(restored.show_keys(), ["1", "3"]);
+
+    // 截断或格式错误
+    assert!(LRUCache::<String, Vec<u32>>::restore(&buf[..buf.len() - 1], 4).is_err());
+    let err = LRUCache::<u32, u32>::restore(&b"LRU0"[..], 4)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // 已过期的元素不写出
+    use crate::sort::lru::ManualClock;
+    use std::time::Duration;
+    let clock = ManualClock::new();
+    cache.set_clock(clock.clone());
+    cache.put_with_ttl("4".to_owned(), vec![4], Duration::from_secs(1));
+    clock.advance(Duration::from_secs(2));
+    buf.clear();
+    cache.snapshot(&mut buf).unwrap();
+    let restored = LRUCache::<String, Vec<u32>>::restore(&buf[..], 4).unwrap();
+    assert_eq!(restored.show_keys(), ["1", "3", "2"]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_lru_snapshot_serde() {
+    let mut cache = LRUCache::new_with_capacity(3);
+    cache.put("a".to_owned(), (1u8, Some(1.5f64)));
+    cache.put("b".to_owned(), (2, None));
+    cache.put("c".to_owned(), (3, None));
+    cache.get("a");
+    let mut buf = Vec::new();
+    cache.snapshot_serde(&mut buf).unwrap();
+    let restored = LRUCache::<String, (u8, Option<f64>)>::restore_serde(&buf[..], 3).unwrap();
+    assert_eq!(restored.show_keys(), ["a", "c", "b"]);
+    assert_eq!(restored.peek("a"), Some(&(1, Some(1.5))));
+}
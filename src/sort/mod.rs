@@ -2,6 +2,8 @@ pub mod dijkstra;
 pub mod heap_sort;
 pub mod lru;
 pub mod lru_lockfree;
+pub mod lru_sharded;
+pub mod lru_snapshot;
 pub mod merge_sort;
 pub mod quick_sort;
 pub mod shell_sort;